@@ -3,7 +3,7 @@ use std::{fmt, str::FromStr};
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
 
-use crate::CmdExecutor;
+use crate::{get_reader, get_writer, CmdExecutor, OutputMode};
 
 use super::verify_file_exists;
 
@@ -20,19 +20,27 @@ pub enum Base64SubCommand {
 pub struct Base64EncodeOpts {
     #[arg(short, long, value_parser = verify_file_exists, default_value = "-")]
     pub input: String,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
     #[arg(short, long, value_parser = parse_base64_format, default_value = "standard")]
     pub format: Base64Format,
 }
 
 impl CmdExecutor for Base64EncodeOpts {
-    async fn execute(self) -> anyhow::Result<()> {
-        crate::process_encode(&self.input, self.format)
+    async fn execute(self, _mode: OutputMode) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+        crate::process_encode(&mut reader, &mut writer, self.format)?;
+        Ok(())
     }
 }
 
 impl CmdExecutor for Base64DecodeOpts {
-    async fn execute(self) -> anyhow::Result<()> {
-        crate::process_decode(&self.input, self.format)
+    async fn execute(self, _mode: OutputMode) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+        crate::process_decode(&mut reader, &mut writer, self.format)?;
+        Ok(())
     }
 }
 
@@ -40,8 +48,11 @@ impl CmdExecutor for Base64DecodeOpts {
 pub struct Base64DecodeOpts {
     #[arg(short, long, value_parser = verify_file_exists, default_value = "-")]
     pub input: String,
-    #[arg(short, long, value_parser = parse_base64_format, default_value = "standard")]
-    pub format: Base64Format,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    /// Alphabet to decode with, detected from the input when omitted
+    #[arg(short, long, value_parser = parse_base64_format)]
+    pub format: Option<Base64Format>,
 }
 
 fn parse_base64_format(format: &str) -> Result<Base64Format, anyhow::Error> {
@@ -51,14 +62,28 @@ fn parse_base64_format(format: &str) -> Result<Base64Format, anyhow::Error> {
 #[derive(Debug, Clone, Copy)]
 pub enum Base64Format {
     Standard,
+    StandardNoPad,
     UrlSafe,
+    UrlSafePad,
+    Mime,
+    Pem,
+}
+
+impl Base64Format {
+    pub fn is_url_safe(self) -> bool {
+        matches!(self, Base64Format::UrlSafe | Base64Format::UrlSafePad)
+    }
 }
 
 impl From<Base64Format> for &'static str {
     fn from(format: Base64Format) -> Self {
         match format {
             Base64Format::Standard => "standard",
+            Base64Format::StandardNoPad => "standard-nopad",
             Base64Format::UrlSafe => "urlsafe",
+            Base64Format::UrlSafePad => "urlsafe-pad",
+            Base64Format::Mime => "mime",
+            Base64Format::Pem => "pem",
         }
     }
 }
@@ -68,7 +93,11 @@ impl FromStr for Base64Format {
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         match value.to_lowercase().as_str() {
             "standard" => Ok(Base64Format::Standard),
-            "urlsafe" => Ok(Base64Format::UrlSafe),
+            "standard-nopad" => Ok(Base64Format::StandardNoPad),
+            "urlsafe" | "urlsafe-nopad" => Ok(Base64Format::UrlSafe),
+            "urlsafe-pad" => Ok(Base64Format::UrlSafePad),
+            "mime" => Ok(Base64Format::Mime),
+            "pem" => Ok(Base64Format::Pem),
             _ => Err(anyhow::anyhow!("Invalid format")),
         }
     }
@@ -2,11 +2,13 @@ use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use enum_dispatch::enum_dispatch;
 
 use crate::{
-    get_content, get_reader, process_chacha_generate, process_decrypt, process_encrypt, CmdExecutor,
+    get_content, get_reader, process_chacha_generate, process_decrypt, process_encrypt,
+    CmdExecutor, OutputMode,
 };
 
-use super::{verify_file_exists, verify_path};
+use super::{print_json, verify_file_exists, verify_path};
 use clap::Parser;
+use serde_json::json;
 use std::path::PathBuf;
 use std::{fmt, str::FromStr};
 
@@ -32,15 +34,20 @@ pub struct EncryptOpts {
 }
 
 impl CmdExecutor for EncryptOpts {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
         let mut reader = get_reader(&self.input)?;
         let key = get_content(&self.key)?;
         let (ret, nonce) = process_encrypt(&mut reader, &key, self.format)?;
         let ret = URL_SAFE_NO_PAD.encode(ret);
 
         let nonce = URL_SAFE_NO_PAD.encode(nonce);
-        println!("Encrypted: {}", ret);
-        println!("Nonce: {}", nonce);
+        match mode {
+            OutputMode::Json => print_json(&json!({ "ciphertext": ret, "nonce": nonce }))?,
+            OutputMode::Text => {
+                println!("Encrypted: {}", ret);
+                println!("Nonce: {}", nonce);
+            }
+        }
         Ok(())
     }
 }
@@ -58,12 +65,15 @@ pub struct DecryptOpts {
 }
 
 impl CmdExecutor for DecryptOpts {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
         let mut reader = get_reader(&self.input)?;
         let key = get_content(&self.key)?;
         let nonce = URL_SAFE_NO_PAD.decode(&self.nonce)?;
-        let result = process_decrypt(&mut reader, &key, &nonce, self.format)?;
-        println!("Decrypted: {:?}", String::from_utf8(result)?);
+        let result = String::from_utf8(process_decrypt(&mut reader, &key, &nonce, self.format)?)?;
+        match mode {
+            OutputMode::Json => print_json(&json!({ "plaintext": result }))?,
+            OutputMode::Text => println!("Decrypted: {:?}", result),
+        }
         Ok(())
     }
 }
@@ -77,8 +87,12 @@ pub struct GenerateOpts {
 }
 
 impl CmdExecutor for GenerateOpts {
-    async fn execute(self) -> anyhow::Result<()> {
-        process_chacha_generate(self.output, self.format)
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let files = process_chacha_generate(self.output, self.format)?;
+        if mode == OutputMode::Json {
+            print_json(&json!({ "files": files }))?;
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,105 @@
+use std::{fmt, str::FromStr};
+
+use clap::Parser;
+
+use crate::{
+    get_reader, get_writer, process_codec_decode, process_codec_encode, CmdExecutor, OutputMode,
+};
+
+use super::verify_file_exists;
+
+#[derive(Debug, Parser)]
+pub struct EncodeOpts {
+    #[arg(short, long, value_parser = verify_file_exists, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    #[arg(short, long, value_parser = parse_codec)]
+    pub codec: Codec,
+}
+
+impl CmdExecutor for EncodeOpts {
+    async fn execute(self, _mode: OutputMode) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+        process_codec_encode(&mut reader, &mut writer, self.codec)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct DecodeOpts {
+    #[arg(short, long, value_parser = verify_file_exists, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    #[arg(short, long, value_parser = parse_codec)]
+    pub codec: Codec,
+}
+
+impl CmdExecutor for DecodeOpts {
+    async fn execute(self, _mode: OutputMode) -> anyhow::Result<()> {
+        let mut reader = get_reader(&self.input)?;
+        let mut writer = get_writer(&self.output)?;
+        process_codec_decode(&mut reader, &mut writer, self.codec)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Hex,
+    Base32,
+    Base32Hex,
+    Base58,
+    Base85,
+    Z85,
+    Url,
+    Html,
+}
+
+fn parse_codec(codec: &str) -> Result<Codec, anyhow::Error> {
+    codec.parse()
+}
+
+impl From<Codec> for &'static str {
+    fn from(codec: Codec) -> Self {
+        match codec {
+            Codec::Hex => "hex",
+            Codec::Base32 => "base32",
+            Codec::Base32Hex => "base32hex",
+            Codec::Base58 => "base58",
+            Codec::Base85 => "base85",
+            Codec::Z85 => "z85",
+            Codec::Url => "url",
+            Codec::Html => "html",
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "hex" => Ok(Codec::Hex),
+            "base32" => Ok(Codec::Base32),
+            "base32hex" => Ok(Codec::Base32Hex),
+            "base58" => Ok(Codec::Base58),
+            "base85" | "ascii85" => Ok(Codec::Base85),
+            "z85" => Ok(Codec::Z85),
+            "url" => Ok(Codec::Url),
+            "html" => Ok(Codec::Html),
+            _ => Err(anyhow::anyhow!("Invalid codec")),
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -0,0 +1,94 @@
+use std::{fmt, str::FromStr};
+
+use clap::Parser;
+
+use crate::{process_convert, CmdExecutor, OutputMode};
+
+use super::verify_file_exists;
+
+#[derive(Debug, Parser)]
+pub struct ConvertOpts {
+    #[arg(short, long, value_parser = verify_file_exists, default_value = "-")]
+    pub input: String,
+
+    /// Input format, detected from the extension or content when omitted
+    #[arg(long, value_parser = parse_data_format)]
+    pub from: Option<DataFormat>,
+
+    #[arg(long, value_parser = parse_data_format)]
+    pub to: DataFormat,
+
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    #[arg(long, default_value_t = false)]
+    pub compact: bool,
+
+    #[arg(long, default_value_t = false)]
+    pub sort_keys: bool,
+}
+
+impl CmdExecutor for ConvertOpts {
+    async fn execute(self, _mode: OutputMode) -> anyhow::Result<()> {
+        let content = process_convert(
+            &self.input,
+            self.from,
+            self.to,
+            !self.compact,
+            self.sort_keys,
+        )?;
+        match self.output {
+            Some(output) => std::fs::write(output, content)?,
+            None => println!("{}", content.trim_end()),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Toml,
+    Ndjson,
+    Json5,
+    Csv,
+}
+
+pub(crate) fn parse_data_format(format: &str) -> Result<DataFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl From<DataFormat> for &'static str {
+    fn from(format: DataFormat) -> Self {
+        match format {
+            DataFormat::Json => "json",
+            DataFormat::Yaml => "yaml",
+            DataFormat::Toml => "toml",
+            DataFormat::Ndjson => "ndjson",
+            DataFormat::Json5 => "json5",
+            DataFormat::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for DataFormat {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "json" => Ok(DataFormat::Json),
+            "yaml" | "yml" => Ok(DataFormat::Yaml),
+            "toml" => Ok(DataFormat::Toml),
+            "ndjson" | "jsonl" => Ok(DataFormat::Ndjson),
+            "json5" => Ok(DataFormat::Json5),
+            "csv" => Ok(DataFormat::Csv),
+            _ => Err(anyhow::anyhow!("Invalid format")),
+        }
+    }
+}
+
+impl fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
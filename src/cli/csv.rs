@@ -1,56 +1,403 @@
 use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
 use std::str::FromStr;
 
-use clap::Parser;
+use clap::{ArgGroup, Args, Parser};
+use enum_dispatch::enum_dispatch;
+use serde_json::json;
 
-use crate::CmdExecutor;
+use crate::{
+    process_csv_concat, process_csv_dedupe, process_csv_join, process_csv_stats,
+    process_csv_validate, write_records, CmdExecutor, ColumnStats, OutputMode, ReadOptions,
+    Records,
+};
 
-use super::verify_file_exists;
+use super::{print_json, render_table, verify_file_exists};
+
+/// `rcli csv -i input.csv` predates the subcommands and still converts.
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct CsvCommand {
+    #[command(subcommand)]
+    pub cmd: Option<CsvSubCommand>,
+
+    #[command(flatten)]
+    pub convert: Option<CsvOpts>,
+}
+
+impl CmdExecutor for CsvCommand {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        match (self.cmd, self.convert) {
+            (Some(cmd), _) => cmd.execute(mode).await,
+            (None, Some(opts)) => opts.execute(mode).await,
+            (None, None) => anyhow::bail!("Missing --input or a csv subcommand"),
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum CsvSubCommand {
+    #[command(name = "convert", about = "Convert CSV to JSON/YAML")]
+    Convert(CsvOpts),
+    #[command(name = "validate", about = "Validate CSV against a YAML schema")]
+    Validate(CsvValidateOpts),
+    #[command(name = "stats", about = "Profile every column of a CSV")]
+    Stats(CsvStatsOpts),
+    #[command(name = "join", about = "Join two CSVs on a key column")]
+    Join(CsvJoinOpts),
+    #[command(name = "concat", about = "Concatenate CSVs, reconciling headers")]
+    Concat(CsvConcatOpts),
+    #[command(name = "dedupe", about = "Drop duplicate rows by key columns")]
+    Dedupe(CsvDedupeOpts),
+}
 
 #[derive(Debug, Parser)]
 pub struct CsvOpts {
     #[arg(short, long, value_parser = verify_file_exists)]
     pub input: String,
 
+    /// Input format, detected from the file extension when omitted
+    #[arg(long, value_parser = parse_input_format)]
+    pub input_format: Option<InputFormat>,
+
+    /// Worksheet name or zero-based index for xlsx input
+    #[arg(long)]
+    pub sheet: Option<String>,
+
+    /// Single ASCII field separator
+    #[arg(short, long, value_parser = parse_delimiter, default_value = ",")]
+    pub delimiter: u8,
+
     #[arg(long, value_parser = parse_format, default_value = "json")]
     pub format: OutputFormat,
 
     #[arg(short, long)]
     pub output: Option<String>,
 
-    #[arg(short, long, default_value_t = ',')]
-    pub delimiter: char,
-
     #[arg(long, default_value_t = true)]
     pub header: bool,
 }
 
 impl CmdExecutor for CsvOpts {
-    async fn execute(self) -> anyhow::Result<()> {
-        let output = if let Some(output) = self.output {
-            output
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        // not flattened from ReadArgs: clap cannot tell a nested flatten apart
+        // when CsvCommand checks whether any of these flags were given
+        let options = ReadOptions {
+            format: self.input_format,
+            sheet: self.sheet.as_deref(),
+            delimiter: self.delimiter,
+        };
+        let records = crate::process_csv(&self.input, &options)?;
+        write_output(&records, self.output, self.format, mode)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(group(ArgGroup::new("kind").args(["left", "inner", "outer"])))]
+pub struct CsvJoinOpts {
+    #[arg(value_parser = verify_file_exists)]
+    pub a: String,
+
+    #[arg(value_parser = verify_file_exists)]
+    pub b: String,
+
+    #[arg(long)]
+    pub on: String,
+
+    /// Keep every row of the first file
+    #[arg(long, default_value_t = false)]
+    pub left: bool,
+
+    /// Keep only rows present in both files (default)
+    #[arg(long, default_value_t = false)]
+    pub inner: bool,
+
+    /// Keep every row of both files
+    #[arg(long, default_value_t = false)]
+    pub outer: bool,
+
+    #[command(flatten)]
+    pub read: ReadArgs,
+
+    #[arg(long, value_parser = parse_format, default_value = "json")]
+    pub format: OutputFormat,
+
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+impl CmdExecutor for CsvJoinOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let kind = if self.left {
+            JoinKind::Left
+        } else if self.outer {
+            JoinKind::Outer
         } else {
-            format!("output.{}", self.format)
+            JoinKind::Inner
         };
-        crate::process_csv(&self.input, output, self.format)
+        let records = process_csv_join(&self.a, &self.b, &self.on, kind, &self.read.options())?;
+        write_output(&records, self.output, self.format, mode)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvConcatOpts {
+    #[arg(required = true, value_parser = verify_file_exists)]
+    pub inputs: Vec<String>,
+
+    #[command(flatten)]
+    pub read: ReadArgs,
+
+    #[arg(long, value_parser = parse_format, default_value = "json")]
+    pub format: OutputFormat,
+
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+impl CmdExecutor for CsvConcatOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let records = process_csv_concat(&self.inputs, &self.read.options())?;
+        write_output(&records, self.output, self.format, mode)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvDedupeOpts {
+    #[arg(short, long, value_parser = verify_file_exists)]
+    pub input: String,
+
+    /// Columns identifying a duplicate, the whole row when omitted
+    #[arg(short, long)]
+    pub key: Vec<String>,
+
+    #[command(flatten)]
+    pub read: ReadArgs,
+
+    #[arg(long, value_parser = parse_format, default_value = "json")]
+    pub format: OutputFormat,
+
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+impl CmdExecutor for CsvDedupeOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let records = process_csv_dedupe(&self.input, &self.key, &self.read.options())?;
+        write_output(&records, self.output, self.format, mode)?;
+        Ok(())
     }
 }
 
+/// Input options shared by every `rcli csv` subcommand.
+#[derive(Debug, Args)]
+pub struct ReadArgs {
+    /// Input format, detected from the file extension when omitted
+    #[arg(long, value_parser = parse_input_format)]
+    pub input_format: Option<InputFormat>,
+
+    /// Worksheet name or zero-based index for xlsx input
+    #[arg(long)]
+    pub sheet: Option<String>,
+
+    /// Single ASCII field separator
+    #[arg(short, long, value_parser = parse_delimiter, default_value = ",")]
+    pub delimiter: u8,
+}
+
+impl ReadArgs {
+    fn options(&self) -> ReadOptions<'_> {
+        ReadOptions {
+            format: self.input_format,
+            sheet: self.sheet.as_deref(),
+            delimiter: self.delimiter,
+        }
+    }
+}
+
+/// Write `records` to `output`, `output.<format>` by default; JSON mode reports
+/// the path and row count as `{"output", "rows"}`.
+fn write_output(
+    records: &Records,
+    output: Option<String>,
+    format: OutputFormat,
+    mode: OutputMode,
+) -> anyhow::Result<()> {
+    let output = output.unwrap_or_else(|| format!("output.{}", format));
+    let mut writer = BufWriter::new(File::create(&output)?);
+    write_records(records, &mut writer, format)?;
+    if mode == OutputMode::Json {
+        print_json(&json!({ "output": output, "rows": records.rows.len() }))?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvValidateOpts {
+    #[arg(short, long, value_parser = verify_file_exists)]
+    pub input: String,
+
+    #[arg(short, long, value_parser = verify_file_exists)]
+    pub schema: String,
+
+    #[command(flatten)]
+    pub read: ReadArgs,
+
+    /// Write the full report as JSON to this file
+    #[arg(short, long)]
+    pub report: Option<String>,
+}
+
+impl CmdExecutor for CsvValidateOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let report = process_csv_validate(&self.input, &self.schema, &self.read.options())?;
+        if let Some(path) = self.report {
+            std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        }
+        match mode {
+            OutputMode::Json => print_json(&report)?,
+            OutputMode::Text => {
+                for v in &report.violations {
+                    match v.row {
+                        Some(row) => {
+                            eprintln!("row {}, column {:?}: {}", row, v.column, v.message)
+                        }
+                        None => eprintln!("header, column {:?}: {}", v.column, v.message),
+                    }
+                }
+            }
+        }
+        if !report.valid {
+            anyhow::bail!("{} violation(s) found", report.violations.len());
+        }
+        if mode == OutputMode::Text {
+            println!("{} rows valid", report.rows);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvStatsOpts {
+    #[arg(short, long, value_parser = verify_file_exists)]
+    pub input: String,
+
+    #[arg(long, value_parser = parse_stats_format, default_value = "table")]
+    pub format: StatsFormat,
+
+    #[command(flatten)]
+    pub read: ReadArgs,
+
+    /// Number of most frequent values to report per column
+    #[arg(long, default_value_t = 5)]
+    pub top: usize,
+
+    /// Estimate distinct counts with HyperLogLog and bound top-k memory
+    #[arg(long, default_value_t = false)]
+    pub approx: bool,
+}
+
+impl CmdExecutor for CsvStatsOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let stats = process_csv_stats(&self.input, &self.read.options(), self.top, self.approx)?;
+        let format = match mode {
+            OutputMode::Json => StatsFormat::Json,
+            OutputMode::Text => self.format,
+        };
+        match format {
+            StatsFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+            StatsFormat::Table => print!("{}", stats_table(&stats)),
+        }
+        Ok(())
+    }
+}
+
+fn stats_table(stats: &[ColumnStats]) -> String {
+    let header = [
+        "column", "type", "count", "nulls", "distinct", "min", "max", "mean", "stddev", "top",
+    ];
+    let fmt_f64 = |v: Option<f64>| v.map(|v| format!("{:.3}", v)).unwrap_or_default();
+    let fmt_value = |v: &Option<serde_json::Value>| match v {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+        None => String::new(),
+    };
+    let rows = stats
+        .iter()
+        .map(|s| {
+            let top = s
+                .top
+                .iter()
+                .map(|t| format!("{}({})", t.value, t.count))
+                .collect::<Vec<_>>()
+                .join(", ");
+            vec![
+                s.name.clone(),
+                Into::<&str>::into(s.column_type).to_string(),
+                s.count.to_string(),
+                s.nulls.to_string(),
+                s.distinct.to_string(),
+                fmt_value(&s.min),
+                fmt_value(&s.max),
+                fmt_f64(s.mean),
+                fmt_f64(s.stddev),
+                top,
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    render_table(&header, &rows)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Outer,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum OutputFormat {
     Json,
     Yaml,
+    Parquet,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum InputFormat {
+    Csv,
+    Xlsx,
+    Parquet,
+}
+
+fn parse_delimiter(delimiter: &str) -> Result<u8, anyhow::Error> {
+    match delimiter.as_bytes() {
+        [byte] if byte.is_ascii() => Ok(*byte),
+        _ => Err(anyhow::anyhow!(
+            "Delimiter must be a single ASCII character"
+        )),
+    }
 }
 
 fn parse_format(format: &str) -> Result<OutputFormat, anyhow::Error> {
     format.parse()
 }
 
+fn parse_input_format(format: &str) -> Result<InputFormat, anyhow::Error> {
+    format.parse()
+}
+
 impl From<OutputFormat> for &'static str {
     fn from(format: OutputFormat) -> Self {
         match format {
             OutputFormat::Yaml => "yaml",
             OutputFormat::Json => "json",
+            OutputFormat::Parquet => "parquet",
         }
     }
 }
@@ -61,13 +408,91 @@ impl FromStr for OutputFormat {
         match value.to_lowercase().as_str() {
             "json" => Ok(OutputFormat::Json),
             "yaml" => Ok(OutputFormat::Yaml),
+            "parquet" => Ok(OutputFormat::Parquet),
             _ => Err(anyhow::anyhow!("Invalid format")),
         }
     }
 }
 
+impl InputFormat {
+    pub fn detect(input: &str) -> Self {
+        let extension = std::path::Path::new(input)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("xlsx" | "xlsm" | "xlsb" | "xls" | "ods") => InputFormat::Xlsx,
+            Some("parquet") => InputFormat::Parquet,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+impl From<InputFormat> for &'static str {
+    fn from(format: InputFormat) -> Self {
+        match format {
+            InputFormat::Csv => "csv",
+            InputFormat::Xlsx => "xlsx",
+            InputFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "csv" => Ok(InputFormat::Csv),
+            "xlsx" => Ok(InputFormat::Xlsx),
+            "parquet" => Ok(InputFormat::Parquet),
+            _ => Err(anyhow::anyhow!("Invalid format")),
+        }
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
 impl fmt::Display for OutputFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         write!(f, "{}", Into::<&str>::into(*self))
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+pub enum StatsFormat {
+    Table,
+    Json,
+}
+
+fn parse_stats_format(format: &str) -> Result<StatsFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl From<StatsFormat> for &'static str {
+    fn from(format: StatsFormat) -> Self {
+        match format {
+            StatsFormat::Table => "table",
+            StatsFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for StatsFormat {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "table" => Ok(StatsFormat::Table),
+            "json" => Ok(StatsFormat::Json),
+            _ => Err(anyhow::anyhow!("Invalid format")),
+        }
+    }
+}
+
+impl fmt::Display for StatsFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
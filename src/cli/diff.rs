@@ -0,0 +1,115 @@
+use std::{fmt, io::IsTerminal, str::FromStr};
+
+use clap::Parser;
+
+use crate::{json_patch, process_diff, CmdExecutor, DiffOp, OutputMode};
+
+use super::{parse_data_format, print_json, verify_file_exists, DataFormat};
+
+#[derive(Debug, Parser)]
+pub struct DiffOpts {
+    #[arg(value_parser = verify_file_exists)]
+    pub a: String,
+
+    #[arg(value_parser = verify_file_exists)]
+    pub b: String,
+
+    /// Input format for both files, detected from the extension or content when omitted
+    #[arg(long, value_parser = parse_data_format)]
+    pub from: Option<DataFormat>,
+
+    /// Match rows of a list (e.g. CSV records) by this column instead of by position;
+    /// paths then hold key values, so it cannot be combined with `--format patch`
+    #[arg(short, long)]
+    pub key: Option<String>,
+
+    #[arg(long, value_parser = parse_diff_format, default_value = "text")]
+    pub format: DiffFormat,
+
+    #[arg(long, default_value_t = false)]
+    pub no_color: bool,
+
+    /// Exit with an error when the documents differ
+    #[arg(long, default_value_t = false)]
+    pub exit_code: bool,
+}
+
+impl CmdExecutor for DiffOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        // keyed rows are matched by key, so their paths are not array indexes
+        if self.key.is_some() && matches!(self.format, DiffFormat::Patch) {
+            anyhow::bail!("--format patch cannot be combined with --key");
+        }
+        let entries = process_diff(&self.a, &self.b, self.from, self.key.as_deref())?;
+        match (mode, self.format) {
+            // the entries rather than a patch, so the shape is the same with `--key`
+            (OutputMode::Json, _) => print_json(&serde_json::json!({ "changes": entries }))?,
+            (OutputMode::Text, DiffFormat::Patch) => {
+                println!("{}", serde_json::to_string_pretty(&json_patch(&entries))?)
+            }
+            (OutputMode::Text, DiffFormat::Text) => {
+                let color = !self.no_color && std::io::stdout().is_terminal();
+                let render = |v: &Option<serde_json::Value>| {
+                    v.as_ref().map(|v| v.to_string()).unwrap_or_default()
+                };
+                for entry in &entries {
+                    let (sign, code, line) = match entry.op {
+                        DiffOp::Add => ("+", "32", render(&entry.new)),
+                        DiffOp::Remove => ("-", "31", render(&entry.old)),
+                        DiffOp::Replace => (
+                            "~",
+                            "33",
+                            format!("{} -> {}", render(&entry.old), render(&entry.new)),
+                        ),
+                    };
+                    let line = format!("{} {}: {}", sign, entry.path, line);
+                    if color {
+                        println!("\x1b[{}m{}\x1b[0m", code, line);
+                    } else {
+                        println!("{}", line);
+                    }
+                }
+            }
+        }
+        if self.exit_code && !entries.is_empty() {
+            anyhow::bail!("{} difference(s) found", entries.len());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DiffFormat {
+    Text,
+    Patch,
+}
+
+fn parse_diff_format(format: &str) -> Result<DiffFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl From<DiffFormat> for &'static str {
+    fn from(format: DiffFormat) -> Self {
+        match format {
+            DiffFormat::Text => "text",
+            DiffFormat::Patch => "patch",
+        }
+    }
+}
+
+impl FromStr for DiffFormat {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "text" => Ok(DiffFormat::Text),
+            "patch" => Ok(DiffFormat::Patch),
+            _ => Err(anyhow::anyhow!("Invalid format")),
+        }
+    }
+}
+
+impl fmt::Display for DiffFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
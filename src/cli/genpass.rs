@@ -1,11 +1,19 @@
+use std::{fmt, str::FromStr};
+
 use clap::Parser;
 
-use crate::CmdExecutor;
+use crate::{CmdExecutor, GeneratedPassword, OutputMode, PassphrasePolicy, PasswordPolicy};
+
+use super::verify_file_exists;
 
 #[derive(Debug, Parser)]
 pub struct GenpassOpts {
-    #[arg(short, long, default_value_t = 16)]
-    pub length: u8,
+    /// Policy file (YAML or JSON) with the organization's password rules
+    #[arg(long, value_parser = verify_file_exists)]
+    pub policy: Option<String>,
+
+    #[arg(short, long)]
+    pub length: Option<usize>,
 
     #[arg(long, default_value_t = false)]
     pub no_uppercase: bool,
@@ -18,17 +26,245 @@ pub struct GenpassOpts {
 
     #[arg(long, default_value_t = false)]
     pub no_symbol: bool,
+
+    /// Symbol characters to draw from instead of the default set;
+    /// passphrases only use them with `--insert-symbol`
+    #[arg(long)]
+    pub symbols: Option<String>,
+
+    /// Leave out look-alike characters such as 0/O and 1/l/I
+    #[arg(long, default_value_t = false)]
+    pub exclude_ambiguous: bool,
+
+    #[arg(long)]
+    pub min_uppercase: Option<usize>,
+
+    #[arg(long)]
+    pub min_lowercase: Option<usize>,
+
+    #[arg(long)]
+    pub min_number: Option<usize>,
+
+    #[arg(long)]
+    pub min_symbol: Option<usize>,
+
+    /// Minimum zxcvbn score (0-4) the password must reach
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+    pub min_score: Option<u8>,
+
+    /// Generate a diceware passphrase of this many words instead of a password
+    #[arg(long, conflicts_with_all = [
+        "policy",
+        "length",
+        "no_uppercase",
+        "no_lowercace",
+        "no_number",
+        "no_symbol",
+        "exclude_ambiguous",
+        "min_uppercase",
+        "min_lowercase",
+        "min_number",
+        "min_symbol",
+    ])]
+    pub words: Option<usize>,
+
+    #[arg(long, default_value = "-", requires = "words")]
+    pub separator: String,
+
+    /// Capitalize the first letter of every word
+    #[arg(long, default_value_t = false, requires = "words")]
+    pub capitalize: bool,
+
+    /// Append a random digit to one of the words
+    #[arg(long, default_value_t = false, requires = "words")]
+    pub insert_number: bool,
+
+    /// Append a random symbol to one of the words
+    #[arg(long, default_value_t = false, requires = "words")]
+    pub insert_symbol: bool,
+
+    /// Number of passwords to generate
+    #[arg(short, long, default_value_t = 1)]
+    pub count: usize,
+
+    #[arg(long, value_parser = parse_genpass_format, default_value = "plain")]
+    pub format: GenpassFormat,
+
+    /// Only output the passwords, without strength estimates
+    #[arg(long, default_value_t = false)]
+    pub no_strength: bool,
+}
+
+impl GenpassOpts {
+    /// Start from the policy file (or the defaults) and apply flags on top of it.
+    fn policy(&self) -> anyhow::Result<PasswordPolicy> {
+        let mut policy = match &self.policy {
+            Some(path) => PasswordPolicy::load(path)?,
+            None => PasswordPolicy::default(),
+        };
+        if let Some(length) = self.length {
+            policy.length = length;
+        }
+        policy.uppercase &= !self.no_uppercase;
+        policy.lowercase &= !self.no_lowercace;
+        policy.number &= !self.no_number;
+        policy.symbol &= !self.no_symbol;
+        if let Some(symbols) = &self.symbols {
+            policy.symbols = symbols.clone();
+        }
+        policy.exclude_ambiguous |= self.exclude_ambiguous;
+        let overrides = [
+            (&mut policy.min_uppercase, self.min_uppercase),
+            (&mut policy.min_lowercase, self.min_lowercase),
+            (&mut policy.min_number, self.min_number),
+            (&mut policy.min_symbol, self.min_symbol),
+        ];
+        for (min, value) in overrides {
+            if let Some(value) = value {
+                *min = value;
+            }
+        }
+        if let Some(min_score) = self.min_score {
+            policy.min_score = min_score;
+        }
+        Ok(policy)
+    }
+
+    fn passphrase_policy(&self, words: usize) -> PassphrasePolicy {
+        let defaults = PassphrasePolicy::default();
+        PassphrasePolicy {
+            words,
+            separator: self.separator.clone(),
+            capitalize: self.capitalize,
+            number: self.insert_number,
+            symbol: self.insert_symbol,
+            symbols: self.symbols.clone().unwrap_or(defaults.symbols),
+            min_score: self.min_score.unwrap_or(defaults.min_score),
+        }
+    }
 }
 
 impl CmdExecutor for GenpassOpts {
-    async fn execute(self) -> anyhow::Result<()> {
-        crate::process_genpass(
-            self.length,
-            self.no_uppercase,
-            self.no_lowercace,
-            self.no_number,
-            self.no_symbol,
-        )?;
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let passwords = match self.words {
+            Some(words) => {
+                if self.symbols.is_some() && !self.insert_symbol {
+                    anyhow::bail!("--symbols requires --insert-symbol when combined with --words");
+                }
+                let policy = self.passphrase_policy(words);
+                (0..self.count)
+                    .map(|_| crate::process_genphrase(&policy, !self.no_strength))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            None => {
+                let policy = self.policy()?;
+                (0..self.count)
+                    .map(|_| crate::process_genpass(&policy, !self.no_strength))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+        let format = match mode {
+            OutputMode::Json => GenpassFormat::Json,
+            OutputMode::Text => self.format,
+        };
+        match format {
+            GenpassFormat::Plain => {
+                for generated in &passwords {
+                    println!("{}", generated.password);
+                    if let (false, Some(strength)) = (self.no_strength, &generated.strength) {
+                        eprintln!(
+                            "Password strength: {}, entropy: {:.1} bits",
+                            strength.score, generated.entropy
+                        );
+                    }
+                }
+            }
+            GenpassFormat::Json if self.no_strength => {
+                let passwords = passwords.iter().map(|p| &p.password).collect::<Vec<_>>();
+                println!("{}", serde_json::to_string_pretty(&passwords)?);
+            }
+            GenpassFormat::Json => println!("{}", serde_json::to_string_pretty(&passwords)?),
+            GenpassFormat::Csv => write_csv(&passwords, self.no_strength)?,
+        }
         Ok(())
     }
 }
+
+fn write_csv(passwords: &[GeneratedPassword], no_strength: bool) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    if no_strength {
+        writer.write_record(["password"])?;
+        for generated in passwords {
+            writer.write_record([&generated.password])?;
+        }
+    } else {
+        writer.write_record([
+            "password",
+            "score",
+            "entropy",
+            "guesses",
+            "online_throttling",
+            "online_no_throttling",
+            "offline_slow_hashing",
+            "offline_fast_hashing",
+        ])?;
+        for generated in passwords {
+            let strength = generated
+                .strength
+                .as_ref()
+                .expect("strength is estimated unless --no-strength");
+            let times = &strength.crack_times;
+            writer.write_record([
+                generated.password.clone(),
+                strength.score.to_string(),
+                format!("{:.1}", generated.entropy),
+                strength.guesses.to_string(),
+                times.online_throttling.clone(),
+                times.online_no_throttling.clone(),
+                times.offline_slow_hashing.clone(),
+                times.offline_fast_hashing.clone(),
+            ])?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum GenpassFormat {
+    Plain,
+    Json,
+    Csv,
+}
+
+fn parse_genpass_format(format: &str) -> Result<GenpassFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl From<GenpassFormat> for &'static str {
+    fn from(format: GenpassFormat) -> Self {
+        match format {
+            GenpassFormat::Plain => "plain",
+            GenpassFormat::Json => "json",
+            GenpassFormat::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for GenpassFormat {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "plain" => Ok(GenpassFormat::Plain),
+            "json" => Ok(GenpassFormat::Json),
+            "csv" => Ok(GenpassFormat::Csv),
+            _ => Err(anyhow::anyhow!("Invalid format")),
+        }
+    }
+}
+
+impl fmt::Display for GenpassFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
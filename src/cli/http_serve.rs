@@ -1,9 +1,14 @@
-use std::path::PathBuf;
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    str::FromStr,
+};
 
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
 
-use crate::CmdExecutor;
+use crate::{CmdExecutor, OutputMode, ServePolicy, TlsSource};
 
 use super::verify_path;
 
@@ -20,10 +25,87 @@ pub struct HttpServeOpts {
     pub dirtory: PathBuf,
     #[arg(short, long, default_value_t = 8080)]
     pub port: u16,
+
+    /// Address to listen on, e.g. 0.0.0.0 or :: for all interfaces
+    #[arg(short, long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    pub bind: IpAddr,
+
+    /// PEM certificate chain to serve HTTPS with
+    #[arg(long, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key matching --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// Serve HTTPS with a freshly generated self-signed certificate
+    #[arg(long, default_value_t = false, conflicts_with = "tls_cert")]
+    pub self_signed: bool,
+
+    /// Serve symlinks that point outside the served directory
+    #[arg(long, default_value_t = false)]
+    pub follow_symlinks: bool,
+
+    /// How to treat files and directories whose name starts with a dot
+    #[arg(long, value_parser = parse_dotfile_policy, default_value = "ignore")]
+    pub dotfiles: DotfilePolicy,
 }
 
 impl CmdExecutor for HttpServeOpts {
-    async fn execute(self) -> anyhow::Result<()> {
-        crate::process_http_serve(self.dirtory, self.port).await
+    async fn execute(self, _mode: OutputMode) -> anyhow::Result<()> {
+        let policy = ServePolicy {
+            follow_symlinks: self.follow_symlinks,
+            dotfiles: self.dotfiles,
+        };
+        let tls = match (self.tls_cert, self.tls_key) {
+            (Some(cert), Some(key)) => Some(TlsSource::Pem { cert, key }),
+            _ if self.self_signed => Some(TlsSource::SelfSigned),
+            _ => None,
+        };
+        let addr = SocketAddr::new(self.bind, self.port);
+        crate::process_http_serve(self.dirtory, addr, tls, policy).await?;
+        Ok(())
+    }
+}
+
+/// `allow` serves dotfiles, `deny` answers 403 and `ignore` pretends they do
+/// not exist; both of the latter hide them from listings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DotfilePolicy {
+    Allow,
+    Deny,
+    #[default]
+    Ignore,
+}
+
+fn parse_dotfile_policy(policy: &str) -> Result<DotfilePolicy, anyhow::Error> {
+    policy.parse()
+}
+
+impl From<DotfilePolicy> for &'static str {
+    fn from(policy: DotfilePolicy) -> Self {
+        match policy {
+            DotfilePolicy::Allow => "allow",
+            DotfilePolicy::Deny => "deny",
+            DotfilePolicy::Ignore => "ignore",
+        }
+    }
+}
+
+impl FromStr for DotfilePolicy {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "allow" => Ok(DotfilePolicy::Allow),
+            "deny" => Ok(DotfilePolicy::Deny),
+            "ignore" => Ok(DotfilePolicy::Ignore),
+            _ => Err(anyhow::anyhow!("Invalid dotfile policy")),
+        }
+    }
+}
+
+impl fmt::Display for DotfilePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
     }
 }
@@ -1,6 +1,9 @@
-use crate::{process_jwt_sign, process_jwt_verify, CmdExecutor};
+use crate::{process_jwt_sign, process_jwt_verify, CmdExecutor, OutputMode};
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
+use serde_json::json;
+
+use super::print_json;
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExecutor)]
@@ -23,9 +26,12 @@ pub struct JwtSignOpts {
 }
 
 impl CmdExecutor for JwtSignOpts {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
         let token = process_jwt_sign(self.sub, self.aud, self.exp)?;
-        println!("Token: {}", token);
+        match mode {
+            OutputMode::Json => print_json(&json!({ "token": token }))?,
+            OutputMode::Text => println!("Token: {}", token),
+        }
 
         Ok(())
     }
@@ -38,12 +44,19 @@ pub struct JwtVerifyOpts {
 }
 
 impl CmdExecutor for JwtVerifyOpts {
-    async fn execute(self) -> anyhow::Result<()> {
-        let verified = process_jwt_verify(self.token)?;
-        if verified {
-            println!("Token verified");
-        } else {
-            println!("Token not verified");
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let token_data = process_jwt_verify(self.token)?;
+        match mode {
+            OutputMode::Json => print_json(&json!({
+                "verified": true,
+                "header": token_data.header,
+                "claims": token_data.claims,
+            }))?,
+            OutputMode::Text => {
+                println!("{:?}", token_data.claims);
+                println!("{:?}", token_data.header);
+                println!("Token verified");
+            }
         }
         Ok(())
     }
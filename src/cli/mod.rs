@@ -1,29 +1,74 @@
 mod base64;
 mod chacha;
+mod codec;
+mod convert;
 mod csv;
+mod diff;
 mod genpass;
 mod http_serve;
 mod jwt;
+mod otp;
+mod password;
+mod query;
+mod schema;
 mod text;
+mod token;
 
 use crate::CmdExecutor;
 
 use clap::Parser;
-use std::path::{Path, PathBuf};
+use serde::Serialize;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-pub use self::{base64::*, chacha::*, csv::*, genpass::*, http_serve::*, jwt::*, text::*};
+pub use self::{
+    base64::*, chacha::*, codec::*, convert::*, csv::*, diff::*, genpass::*, http_serve::*, jwt::*,
+    otp::*, password::*, query::*, schema::*, text::*, token::*,
+};
 
 #[derive(Debug, Parser)]
 #[command(name = "rcli", version, author, about, long_about = None)]
 pub struct Opts {
+    /// Print results as JSON; data written by encode, convert and the like is left as is
+    #[arg(long, global = true, value_parser = parse_output_mode, default_value = "text")]
+    pub output_format: OutputMode,
+
     #[command(subcommand)]
     pub cmd: SubCommand,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    Text,
+    Json,
+}
+
 #[derive(Debug, Parser)]
 pub enum SubCommand {
-    #[command(name = "csv", about = "Convert CSV to JSON.")]
-    Csv(CsvOpts),
+    #[command(name = "csv", about = "CSV convert/validate/stats/join/concat/dedupe")]
+    Csv(CsvCommand),
+    #[command(
+        name = "convert",
+        about = "Convert between JSON, YAML, TOML, NDJSON and JSON5"
+    )]
+    Convert(ConvertOpts),
+    #[command(
+        name = "encode",
+        about = "Encode with hex/base32/base58/base85/z85/url/html"
+    )]
+    Encode(EncodeOpts),
+    #[command(
+        name = "decode",
+        about = "Decode hex/base32/base58/base85/z85/url/html"
+    )]
+    Decode(DecodeOpts),
+    #[command(name = "diff", about = "Structural diff of JSON/YAML/TOML/CSV files")]
+    Diff(DiffOpts),
+    #[command(subcommand, about = "Generate random tokens and IDs")]
+    Gen(GenSubCommand),
     #[command(name = "genpass", about = "Generate rand password")]
     GenPass(GenpassOpts),
     #[command(subcommand, about = "Base64 encode/decode")]
@@ -36,18 +81,82 @@ pub enum SubCommand {
     Http(HttpSubCommand),
     #[command(subcommand, about = "JWT sign/verify")]
     Jwt(JwtSubCommand),
+    #[command(subcommand, about = "TOTP/HOTP one-time passwords")]
+    Otp(OtpSubCommand),
+    #[command(subcommand, about = "Password strength audit")]
+    Password(PasswordSubCommand),
+    #[command(name = "query", about = "Query JSON/YAML/TOML data with JSONPath")]
+    Query(QueryOpts),
+    #[command(subcommand, about = "JSON Schema validate/infer")]
+    Schema(SchemaSubCommand),
 }
 
 impl CmdExecutor for SubCommand {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
         match self {
-            SubCommand::Csv(opts) => opts.execute().await,
-            SubCommand::GenPass(opts) => opts.execute().await,
-            SubCommand::Base64(cmd) => cmd.execute().await,
-            SubCommand::Text(cmd) => cmd.execute().await,
-            SubCommand::Chacha(cmd) => cmd.execute().await,
-            SubCommand::Http(cmd) => cmd.execute().await,
-            SubCommand::Jwt(cmd) => cmd.execute().await,
+            SubCommand::Csv(opts) => opts.execute(mode).await,
+            SubCommand::Convert(opts) => opts.execute(mode).await,
+            SubCommand::Encode(opts) => opts.execute(mode).await,
+            SubCommand::Decode(opts) => opts.execute(mode).await,
+            SubCommand::Diff(opts) => opts.execute(mode).await,
+            SubCommand::Gen(cmd) => cmd.execute(mode).await,
+            SubCommand::GenPass(opts) => opts.execute(mode).await,
+            SubCommand::Base64(cmd) => cmd.execute(mode).await,
+            SubCommand::Text(cmd) => cmd.execute(mode).await,
+            SubCommand::Chacha(cmd) => cmd.execute(mode).await,
+            SubCommand::Http(cmd) => cmd.execute(mode).await,
+            SubCommand::Jwt(cmd) => cmd.execute(mode).await,
+            SubCommand::Otp(cmd) => cmd.execute(mode).await,
+            SubCommand::Password(cmd) => cmd.execute(mode).await,
+            SubCommand::Query(opts) => opts.execute(mode).await,
+            SubCommand::Schema(cmd) => cmd.execute(mode).await,
+        }
+    }
+}
+
+fn parse_output_mode(mode: &str) -> Result<OutputMode, anyhow::Error> {
+    mode.parse()
+}
+
+impl From<OutputMode> for &'static str {
+    fn from(mode: OutputMode) -> Self {
+        match mode {
+            OutputMode::Text => "text",
+            OutputMode::Json => "json",
+        }
+    }
+}
+
+impl FromStr for OutputMode {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "text" => Ok(OutputMode::Text),
+            "json" => Ok(OutputMode::Json),
+            _ => Err(anyhow::anyhow!("Invalid output format")),
+        }
+    }
+}
+
+impl fmt::Display for OutputMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+/// Print `value` as pretty JSON, the shape used by `--output-format json`.
+fn print_json(value: &impl Serialize) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Print a single value, as `{"<field>": "<value>"}` in JSON mode.
+fn print_value(mode: OutputMode, field: &str, value: impl fmt::Display) -> anyhow::Result<()> {
+    match mode {
+        OutputMode::Json => print_json(&serde_json::json!({ field: value.to_string() })),
+        OutputMode::Text => {
+            println!("{}", value);
+            Ok(())
         }
     }
 }
@@ -69,6 +178,31 @@ fn verify_path(filepath: &str) -> Result<PathBuf, &'static str> {
     }
 }
 
+/// Render rows as left-aligned columns separated by two spaces.
+fn render_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = header.iter().map(|h| h.chars().count()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    let mut push_row = |cells: &mut dyn Iterator<Item = &str>| {
+        let line = cells
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        table.push_str(line.trim_end());
+        table.push('\n');
+    };
+    push_row(&mut header.iter().copied());
+    for row in rows {
+        push_row(&mut row.iter().map(String::as_str));
+    }
+    table
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,4 +214,110 @@ mod tests {
         assert_eq!(verify_file_exists("Cargo.toml"), Ok("Cargo.toml".into()));
         assert_eq!(verify_file_exists("not-exist"), Err("File does not exists"));
     }
+
+    #[test]
+    fn test_global_output_format() {
+        let opts = Opts::try_parse_from(["rcli", "gen", "uuid", "--output-format", "json"]);
+        assert_eq!(opts.expect("parse").output_format, OutputMode::Json);
+        let opts = Opts::try_parse_from(["rcli", "--output-format", "JSON", "gen", "ulid"]);
+        assert_eq!(opts.expect("parse").output_format, OutputMode::Json);
+        let opts = Opts::try_parse_from(["rcli", "gen", "ulid"]);
+        assert_eq!(opts.expect("parse").output_format, OutputMode::Text);
+        assert!(Opts::try_parse_from(["rcli", "--output-format", "xml", "gen", "ulid"]).is_err());
+    }
+
+    #[test]
+    fn test_genpass_words_conflicts() {
+        let genpass = |args: &[&str]| Opts::try_parse_from(["rcli", "genpass"].iter().chain(args));
+        assert!(genpass(&["--words", "5", "--insert-symbol", "--symbols", "#"]).is_ok());
+        for flag in [
+            "--no-uppercase",
+            "--no-symbol",
+            "--exclude-ambiguous",
+            "--min-number=2",
+        ] {
+            assert!(genpass(&["--words", "5", flag]).is_err(), "{}", flag);
+        }
+    }
+
+    #[test]
+    fn test_csv_without_subcommand_converts() {
+        let csv = |args: &[&str]| {
+            Opts::try_parse_from(["rcli", "csv"].iter().chain(args)).map(|opts| match opts.cmd {
+                SubCommand::Csv(csv) => csv,
+                _ => unreachable!(),
+            })
+        };
+        let legacy = csv(&["-i", "Cargo.toml"]).expect("legacy flags");
+        assert!(legacy.cmd.is_none());
+        assert_eq!(legacy.convert.expect("convert opts").input, "Cargo.toml");
+        let stats = csv(&["stats", "-i", "Cargo.toml"]).expect("subcommand");
+        assert!(matches!(stats.cmd, Some(CsvSubCommand::Stats(_))));
+        assert!(csv(&["-i", "Cargo.toml", "stats"]).is_err());
+    }
+
+    #[test]
+    fn test_csv_delimiter() {
+        let stats = |delimiter: &str| {
+            let args = ["rcli", "csv", "stats", "-i", "Cargo.toml", "-d", delimiter];
+            Opts::try_parse_from(args).map(|opts| match opts.cmd {
+                SubCommand::Csv(CsvCommand {
+                    cmd: Some(CsvSubCommand::Stats(opts)),
+                    ..
+                }) => opts.read.delimiter,
+                _ => unreachable!(),
+            })
+        };
+        assert_eq!(stats(";").expect("ascii"), b';');
+        assert_eq!(stats("\t").expect("tab"), b'\t');
+        assert!(stats("§").is_err());
+        assert!(stats("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_csv_join_with_delimiter() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        std::fs::write(path("l.csv"), "id;name\n1;a\n2;b\n")?;
+        std::fs::write(path("r.csv"), "id;kit\n2;9\n")?;
+        let (left, right, output) = (path("l.csv"), path("r.csv"), path("out.json"));
+        let args = [
+            "rcli", "csv", "join", &left, &right, "--on", "id", "-d", ";",
+        ];
+        let opts = Opts::try_parse_from(args.into_iter().chain(["-o", &output]))?;
+        opts.cmd.execute(OutputMode::Text).await?;
+
+        let rows: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output)?)?;
+        assert_eq!(
+            rows,
+            serde_json::json!([{ "id": "2", "name": "b", "kit": "9" }])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_http_serve_bind_and_tls() {
+        let serve = |args: &[&str]| {
+            let base = ["rcli", "http", "serve", "-d", "."];
+            Opts::try_parse_from(base.iter().chain(args)).map(|opts| match opts.cmd {
+                SubCommand::Http(HttpSubCommand::Serve(opts)) => opts,
+                _ => unreachable!(),
+            })
+        };
+        let opts = serve(&[]).expect("defaults");
+        assert_eq!(opts.bind.to_string(), "127.0.0.1");
+        assert!(opts.tls_cert.is_none() && !opts.self_signed);
+        assert_eq!(
+            serve(&["--bind", "::"]).expect("ipv6").bind.to_string(),
+            "::"
+        );
+        assert!(serve(&["--bind", "localhost"]).is_err());
+        assert!(serve(&["--tls-cert", "cert.pem"]).is_err());
+        assert!(serve(&["--tls-cert", "c.pem", "--tls-key", "k.pem", "--self-signed"]).is_err());
+        assert!(
+            serve(&["--self-signed", "--bind", "0.0.0.0"])
+                .expect("self-signed")
+                .self_signed
+        );
+    }
 }
@@ -0,0 +1,208 @@
+use std::{fmt, str::FromStr};
+
+use clap::{Args, Parser};
+use enum_dispatch::enum_dispatch;
+use serde_json::json;
+
+use crate::{
+    process_hotp, process_otp_secret, process_otp_uri, process_totp, process_totp_verify,
+    CmdExecutor, OtpParams, OutputMode, MAX_WINDOW,
+};
+
+use super::{print_json, print_value};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum OtpSubCommand {
+    #[command(about = "Generate a base32 secret and otpauth:// provisioning URI")]
+    Secret(OtpSecretOpts),
+    #[command(about = "Compute the TOTP (or HOTP with --counter) code for a secret")]
+    Code(OtpCodeOpts),
+    #[command(about = "Verify a TOTP code, allowing for clock drift")]
+    Verify(OtpVerifyOpts),
+}
+
+#[derive(Debug, Args)]
+pub struct OtpParamOpts {
+    #[arg(short, long, value_parser = parse_otp_algorithm, default_value = "sha1")]
+    pub algorithm: OtpAlgorithm,
+
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(6..=8), default_value_t = 6)]
+    pub digits: u32,
+
+    /// TOTP time step in seconds
+    #[arg(short, long, default_value_t = 30)]
+    pub period: u64,
+}
+
+impl From<&OtpParamOpts> for OtpParams {
+    fn from(opts: &OtpParamOpts) -> Self {
+        Self {
+            algorithm: opts.algorithm,
+            digits: opts.digits,
+            period: opts.period,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpSecretOpts {
+    /// Secret size in bytes
+    #[arg(short, long, default_value_t = 20)]
+    pub bytes: usize,
+
+    /// Account name for the provisioning URI, e.g. an email address
+    #[arg(long)]
+    pub account: Option<String>,
+
+    #[arg(long, requires = "account")]
+    pub issuer: Option<String>,
+
+    /// Describe an HOTP token starting at this counter instead of a TOTP one
+    #[arg(long, requires = "account")]
+    pub counter: Option<u64>,
+
+    #[command(flatten)]
+    pub params: OtpParamOpts,
+}
+
+impl CmdExecutor for OtpSecretOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let secret = process_otp_secret(self.bytes)?;
+        let uri = match &self.account {
+            Some(account) => Some(process_otp_uri(
+                &secret,
+                account,
+                self.issuer.as_deref(),
+                (&self.params).into(),
+                self.counter,
+            )?),
+            None => None,
+        };
+        match mode {
+            OutputMode::Json => print_json(&json!({ "secret": secret, "uri": uri }))?,
+            OutputMode::Text => {
+                println!("{}", secret);
+                if let Some(uri) = uri {
+                    println!("{}", uri);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpCodeOpts {
+    /// Base32 secret
+    #[arg(short, long)]
+    pub secret: String,
+
+    /// Compute an HOTP code for this counter instead of a TOTP code
+    #[arg(short, long, conflicts_with = "time")]
+    pub counter: Option<u64>,
+
+    /// Unix time to compute the TOTP code for, defaults to now
+    #[arg(short, long)]
+    pub time: Option<u64>,
+
+    #[command(flatten)]
+    pub params: OtpParamOpts,
+}
+
+impl CmdExecutor for OtpCodeOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let params = (&self.params).into();
+        let code = match self.counter {
+            Some(counter) => process_hotp(&self.secret, counter, params)?,
+            None => process_totp(&self.secret, self.time, params)?,
+        };
+        print_value(mode, "code", code)
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpVerifyOpts {
+    /// Base32 secret
+    #[arg(short, long)]
+    pub secret: String,
+
+    #[arg(long)]
+    pub code: String,
+
+    /// Number of time steps before and after now to accept
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(0..=MAX_WINDOW), default_value_t = 1)]
+    pub window: u64,
+
+    /// Unix time to verify against, defaults to now
+    #[arg(short, long)]
+    pub time: Option<u64>,
+
+    #[command(flatten)]
+    pub params: OtpParamOpts,
+}
+
+impl CmdExecutor for OtpVerifyOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let matched = process_totp_verify(
+            &self.secret,
+            &self.code,
+            self.time,
+            self.window,
+            (&self.params).into(),
+        )?;
+        match (mode, matched) {
+            (OutputMode::Json, _) => {
+                print_json(&json!({ "valid": matched.is_some(), "drift": matched }))?
+            }
+            (OutputMode::Text, Some(0)) => println!("Code valid"),
+            (OutputMode::Text, Some(offset)) => {
+                println!("Code valid, {} step(s) of drift", offset)
+            }
+            (OutputMode::Text, None) => {}
+        }
+        if matched.is_none() {
+            anyhow::bail!("Code invalid");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+fn parse_otp_algorithm(algorithm: &str) -> Result<OtpAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+impl From<OtpAlgorithm> for &'static str {
+    fn from(algorithm: OtpAlgorithm) -> Self {
+        match algorithm {
+            OtpAlgorithm::Sha1 => "sha1",
+            OtpAlgorithm::Sha256 => "sha256",
+            OtpAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "sha1" => Ok(OtpAlgorithm::Sha1),
+            "sha256" => Ok(OtpAlgorithm::Sha256),
+            "sha512" => Ok(OtpAlgorithm::Sha512),
+            _ => Err(anyhow::anyhow!("Invalid algorithm")),
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -0,0 +1,154 @@
+use std::{fmt, str::FromStr};
+
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+
+use crate::{process_password_check, CmdExecutor, OutputMode, PasswordAudit};
+
+use super::{render_table, verify_file_exists};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum PasswordSubCommand {
+    #[command(about = "Audit the strength of passwords, one per line")]
+    Check(PasswordCheckOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct PasswordCheckOpts {
+    #[arg(short, long, value_parser = verify_file_exists, default_value = "-")]
+    pub input: String,
+
+    /// Word zxcvbn should treat as easily guessed, such as a user or company name
+    #[arg(short, long = "user-input")]
+    pub user_inputs: Vec<String>,
+
+    /// File of extra dictionary words, one per line
+    #[arg(long, value_parser = verify_file_exists)]
+    pub dictionary: Option<String>,
+
+    #[arg(long, value_parser = parse_check_format, default_value = "table")]
+    pub format: CheckFormat,
+
+    /// Fail when any password scores below this (0-4)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4), default_value_t = 3)]
+    pub min_score: u8,
+
+    /// Include the passwords themselves in the report
+    #[arg(long, default_value_t = false)]
+    pub reveal: bool,
+}
+
+impl CmdExecutor for PasswordCheckOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let audit = process_password_check(
+            &self.input,
+            &self.user_inputs,
+            self.dictionary.as_deref(),
+            self.min_score,
+            self.reveal,
+        )?;
+        let format = match mode {
+            OutputMode::Json => CheckFormat::Json,
+            OutputMode::Text => self.format,
+        };
+        match format {
+            CheckFormat::Json => println!("{}", serde_json::to_string_pretty(&audit)?),
+            CheckFormat::Table => print!("{}", audit_table(&audit)),
+        }
+        if audit.summary.below_min_score > 0 {
+            anyhow::bail!(
+                "{} password(s) below score {}",
+                audit.summary.below_min_score,
+                self.min_score
+            );
+        }
+        Ok(())
+    }
+}
+
+fn audit_table(audit: &PasswordAudit) -> String {
+    let mut header = vec!["line", "score", "guesses", "offline slow", "offline fast"];
+    if audit.passwords.iter().any(|p| p.password.is_some()) {
+        header.insert(1, "password");
+    }
+    header.push("feedback");
+    let rows = audit
+        .passwords
+        .iter()
+        .map(|p| {
+            let times = &p.strength.crack_times;
+            let feedback = p
+                .strength
+                .warning
+                .iter()
+                .chain(&p.strength.suggestions)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let mut row = vec![
+                p.line.to_string(),
+                p.strength.score.to_string(),
+                p.strength.guesses.to_string(),
+                times.offline_slow_hashing.clone(),
+                times.offline_fast_hashing.clone(),
+                feedback,
+            ];
+            if let Some(password) = &p.password {
+                row.insert(1, password.clone());
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+
+    let summary = &audit.summary;
+    let mut table = render_table(&header, &rows);
+    table.push_str(&format!(
+        "\n{} password(s), {} blank line(s), {} reused\n",
+        summary.total, summary.blank, summary.reused
+    ));
+    for (score, count) in summary.scores.iter().enumerate() {
+        table.push_str(&format!("score {}: {}\n", score, count));
+    }
+    table.push_str(&format!(
+        "below score {}: {}\n",
+        summary.min_score, summary.below_min_score
+    ));
+    table
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CheckFormat {
+    Table,
+    Json,
+}
+
+fn parse_check_format(format: &str) -> Result<CheckFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl From<CheckFormat> for &'static str {
+    fn from(format: CheckFormat) -> Self {
+        match format {
+            CheckFormat::Table => "table",
+            CheckFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for CheckFormat {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "table" => Ok(CheckFormat::Table),
+            "json" => Ok(CheckFormat::Json),
+            _ => Err(anyhow::anyhow!("Invalid format")),
+        }
+    }
+}
+
+impl fmt::Display for CheckFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -0,0 +1,32 @@
+use clap::Parser;
+
+use crate::{process_query, CmdExecutor, OutputMode};
+
+use super::{parse_data_format, verify_file_exists, DataFormat};
+
+#[derive(Debug, Parser)]
+pub struct QueryOpts {
+    /// JSONPath expression, e.g. '$.players[?(@.position=="Goalkeeper")].name'
+    pub query: String,
+
+    #[arg(short, long, value_parser = verify_file_exists, default_value = "-")]
+    pub input: String,
+
+    /// Input format, detected from the extension or content when omitted
+    #[arg(long, value_parser = parse_data_format)]
+    pub from: Option<DataFormat>,
+
+    #[arg(long, value_parser = parse_data_format, default_value = "json")]
+    pub to: DataFormat,
+
+    #[arg(long, default_value_t = false)]
+    pub compact: bool,
+}
+
+impl CmdExecutor for QueryOpts {
+    async fn execute(self, _mode: OutputMode) -> anyhow::Result<()> {
+        let content = process_query(&self.query, &self.input, self.from, self.to, !self.compact)?;
+        println!("{}", content.trim_end());
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use serde_json::json;
+
+use crate::{process_schema_infer, process_schema_validate, CmdExecutor, OutputMode};
+
+use super::{parse_data_format, print_json, verify_file_exists, DataFormat};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum SchemaSubCommand {
+    #[command(about = "Validate documents against a JSON Schema (draft 2020-12)")]
+    Validate(SchemaValidateOpts),
+    #[command(about = "Infer a JSON Schema from sample documents")]
+    Infer(SchemaInferOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct SchemaValidateOpts {
+    #[arg(short, long, value_parser = verify_file_exists)]
+    pub schema: String,
+
+    #[arg(short, long, value_parser = verify_file_exists, default_value = "-")]
+    pub input: String,
+
+    /// Input format, detected from the extension or content when omitted
+    #[arg(long, value_parser = parse_data_format)]
+    pub from: Option<DataFormat>,
+}
+
+impl CmdExecutor for SchemaValidateOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let errors = process_schema_validate(&self.schema, &self.input, self.from)?;
+        match mode {
+            OutputMode::Json => {
+                print_json(&json!({ "valid": errors.is_empty(), "errors": errors }))?
+            }
+            OutputMode::Text => {
+                for error in &errors {
+                    eprintln!(
+                        "document {}, {}: {}",
+                        error.document, error.path, error.message
+                    );
+                }
+            }
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("{} schema error(s) found", errors.len());
+        }
+        if mode == OutputMode::Text {
+            println!("Document valid");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct SchemaInferOpts {
+    #[arg(short, long, value_parser = verify_file_exists, default_value = "-")]
+    pub input: Vec<String>,
+
+    /// Input format, detected from the extension or content when omitted
+    #[arg(long, value_parser = parse_data_format)]
+    pub from: Option<DataFormat>,
+
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+impl CmdExecutor for SchemaInferOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let schema = process_schema_infer(&self.input, self.from)?;
+        let content = serde_json::to_string_pretty(&schema)?;
+        match self.output {
+            Some(output) => {
+                std::fs::write(&output, content)?;
+                if mode == OutputMode::Json {
+                    print_json(&json!({ "output": output }))?;
+                }
+            }
+            None => println!("{}", content),
+        }
+        Ok(())
+    }
+}
@@ -3,10 +3,14 @@ use std::{fmt, path::PathBuf, str::FromStr};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
+use serde_json::json;
 
-use crate::{get_content, get_reader, process_generate, process_sign, process_verify, CmdExecutor};
+use crate::{
+    get_content, get_reader, process_generate, process_sign, process_verify, CmdExecutor,
+    OutputMode,
+};
 
-use super::{verify_file_exists, verify_path};
+use super::{print_json, verify_file_exists, verify_path};
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExecutor)]
@@ -31,11 +35,14 @@ pub struct TextSignOpts {
 }
 
 impl CmdExecutor for TextSignOpts {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
         let mut reader = get_reader(&self.input)?;
         let key = get_content(&self.key)?;
         let encode = process_sign(&mut reader, &key, self.format)?;
-        println!("Signature result: {}", encode);
+        match mode {
+            OutputMode::Json => print_json(&json!({ "signature": encode }))?,
+            OutputMode::Text => println!("Signature result: {}", encode),
+        }
         Ok(())
     }
 }
@@ -53,15 +60,15 @@ pub struct TextVerifyOpts {
 }
 
 impl CmdExecutor for TextVerifyOpts {
-    async fn execute(self) -> anyhow::Result<()> {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
         let mut reader = get_reader(&self.input)?;
         let key = get_content(&self.key)?;
         let decoded = URL_SAFE_NO_PAD.decode(&self.sig)?;
         let verified = process_verify(&mut reader, &key, &decoded, self.format)?;
-        if verified {
-            println!("Signature verified");
-        } else {
-            println!("Signature not verified");
+        match mode {
+            OutputMode::Json => print_json(&json!({ "verified": verified }))?,
+            OutputMode::Text if verified => println!("Signature verified"),
+            OutputMode::Text => println!("Signature not verified"),
         }
         Ok(())
     }
@@ -76,8 +83,12 @@ pub struct TextGenerateOpts {
 }
 
 impl CmdExecutor for TextGenerateOpts {
-    async fn execute(self) -> anyhow::Result<()> {
-        process_generate(self.output, self.format)
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        let files = process_generate(self.output, self.format)?;
+        if mode == OutputMode::Json {
+            print_json(&json!({ "files": files }))?;
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,155 @@
+use std::{fmt, str::FromStr};
+
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+
+use super::print_value;
+
+use crate::{
+    process_gen_nanoid, process_gen_token, process_gen_ulid, process_gen_uuid, CmdExecutor,
+    OutputMode, NANOID_ALPHABET,
+};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum GenSubCommand {
+    #[command(about = "Generate a random API token")]
+    Token(GenTokenOpts),
+    #[command(about = "Generate a UUID")]
+    Uuid(GenUuidOpts),
+    #[command(about = "Generate a ULID")]
+    Ulid(GenUlidOpts),
+    #[command(about = "Generate a nanoid")]
+    Nanoid(GenNanoidOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GenTokenOpts {
+    /// Number of random bytes
+    #[arg(short, long, default_value_t = 32)]
+    pub bytes: usize,
+
+    #[arg(short, long, value_parser = parse_token_encoding, default_value = "base64url")]
+    pub encoding: TokenEncoding,
+}
+
+impl CmdExecutor for GenTokenOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        print_value(mode, "token", process_gen_token(self.bytes, self.encoding)?)
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct GenUuidOpts {
+    #[arg(short = 'v', long = "version", value_parser = parse_uuid_version, default_value = "4")]
+    pub uuid_version: UuidVersion,
+}
+
+impl CmdExecutor for GenUuidOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        print_value(mode, "uuid", process_gen_uuid(self.uuid_version)?)
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct GenUlidOpts {}
+
+impl CmdExecutor for GenUlidOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        print_value(mode, "ulid", process_gen_ulid()?)
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct GenNanoidOpts {
+    #[arg(short, long, default_value = NANOID_ALPHABET)]
+    pub alphabet: String,
+
+    #[arg(short, long, default_value_t = 21)]
+    pub size: usize,
+}
+
+impl CmdExecutor for GenNanoidOpts {
+    async fn execute(self, mode: OutputMode) -> anyhow::Result<()> {
+        print_value(
+            mode,
+            "nanoid",
+            process_gen_nanoid(&self.alphabet, self.size)?,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TokenEncoding {
+    Base64Url,
+    Hex,
+    Base32,
+}
+
+fn parse_token_encoding(encoding: &str) -> Result<TokenEncoding, anyhow::Error> {
+    encoding.parse()
+}
+
+impl From<TokenEncoding> for &'static str {
+    fn from(encoding: TokenEncoding) -> Self {
+        match encoding {
+            TokenEncoding::Base64Url => "base64url",
+            TokenEncoding::Hex => "hex",
+            TokenEncoding::Base32 => "base32",
+        }
+    }
+}
+
+impl FromStr for TokenEncoding {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "base64url" => Ok(TokenEncoding::Base64Url),
+            "hex" => Ok(TokenEncoding::Hex),
+            "base32" => Ok(TokenEncoding::Base32),
+            _ => Err(anyhow::anyhow!("Invalid encoding")),
+        }
+    }
+}
+
+impl fmt::Display for TokenEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UuidVersion {
+    V4,
+    V7,
+}
+
+fn parse_uuid_version(version: &str) -> Result<UuidVersion, anyhow::Error> {
+    version.parse()
+}
+
+impl From<UuidVersion> for &'static str {
+    fn from(version: UuidVersion) -> Self {
+        match version {
+            UuidVersion::V4 => "4",
+            UuidVersion::V7 => "7",
+        }
+    }
+}
+
+impl FromStr for UuidVersion {
+    type Err = anyhow::Error;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().trim_start_matches('v') {
+            "4" => Ok(UuidVersion::V4),
+            "7" => Ok(UuidVersion::V7),
+            _ => Err(anyhow::anyhow!("Invalid UUID version, expected 4 or 7")),
+        }
+    }
+}
+
+impl fmt::Display for UuidVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
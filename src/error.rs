@@ -0,0 +1,203 @@
+use std::io;
+
+use thiserror::Error;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Error returned by the `process_*` library functions.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Key(#[from] KeyError),
+    #[error(transparent)]
+    Crypto(#[from] CryptoError),
+    #[error(transparent)]
+    Format(#[from] FormatError),
+    /// An argument or policy is out of range or inconsistent.
+    #[error("{0}")]
+    InvalidInput(String),
+}
+
+#[derive(Debug, Error)]
+pub enum KeyError {
+    #[error("Key must be {expected} bytes, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("Invalid key: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Error)]
+pub enum CryptoError {
+    #[error("Encrypt failed")]
+    Encrypt,
+    #[error("Decrypt failed")]
+    Decrypt,
+    #[error("Signature must be {expected} bytes, got {actual}")]
+    SignatureLength { expected: usize, actual: usize },
+    #[error("Nonce must be {expected} bytes, got {actual}")]
+    NonceLength { expected: usize, actual: usize },
+    #[error("{0} is not supported yet")]
+    Unsupported(&'static str),
+    #[error("TLS setup failed: {0}")]
+    Tls(String),
+    #[error(transparent)]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+}
+
+/// The input could not be parsed, or does not have the expected shape.
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error(transparent)]
+    Base64(#[from] base64::DecodeError),
+    #[error(transparent)]
+    Encoding(#[from] data_encoding::DecodeError),
+    #[error(transparent)]
+    Base58(#[from] bs58::decode::Error),
+    #[error(transparent)]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Json5(#[from] json5::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error(transparent)]
+    TomlSerialize(#[from] toml::ser::Error),
+    #[error(transparent)]
+    Xlsx(#[from] calamine::Error),
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[error(transparent)]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+impl Error {
+    pub(crate) fn invalid_input(message: impl Into<String>) -> Self {
+        Error::InvalidInput(message.into())
+    }
+
+    pub(crate) fn invalid_format(message: impl Into<String>) -> Self {
+        Error::Format(FormatError::Invalid(message.into()))
+    }
+
+    /// Process exit code for this kind of failure; 2 matches clap's usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidInput(_) => 2,
+            Error::Io(_) => 3,
+            Error::Format(_) => 4,
+            Error::Key(_) => 5,
+            Error::Crypto(_) => 6,
+        }
+    }
+}
+
+macro_rules! impl_from_format_error {
+    ($($ty:ty),*) => {
+        $(impl From<$ty> for Error {
+            fn from(err: $ty) -> Self {
+                Error::Format(err.into())
+            }
+        })*
+    };
+}
+
+impl_from_format_error!(
+    base64::DecodeError,
+    data_encoding::DecodeError,
+    bs58::decode::Error,
+    std::str::Utf8Error,
+    std::string::FromUtf8Error,
+    serde_json::Error,
+    json5::Error,
+    serde_yaml::Error,
+    toml::de::Error,
+    toml::ser::Error
+);
+
+// Readers for these formats also report I/O failures, which keep the I/O exit code.
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Self {
+        if !err.is_io_error() {
+            return Error::Format(err.into());
+        }
+        match err.into_kind() {
+            csv::ErrorKind::Io(err) => Error::Io(err),
+            _ => unreachable!("checked by is_io_error"),
+        }
+    }
+}
+
+impl From<calamine::Error> for Error {
+    fn from(err: calamine::Error) -> Self {
+        match err {
+            calamine::Error::Io(err) | calamine::Error::Xlsx(calamine::XlsxError::Io(err)) => {
+                Error::Io(err)
+            }
+            err => Error::Format(err.into()),
+        }
+    }
+}
+
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(err: parquet::errors::ParquetError) -> Self {
+        use parquet::errors::ParquetError;
+        match err {
+            ParquetError::External(err) => match err.downcast::<io::Error>() {
+                Ok(err) => Error::Io(*err),
+                Err(err) => Error::Format(ParquetError::External(err).into()),
+            },
+            err => Error::Format(err.into()),
+        }
+    }
+}
+
+impl From<arrow_schema::ArrowError> for Error {
+    fn from(err: arrow_schema::ArrowError) -> Self {
+        match err {
+            arrow_schema::ArrowError::IoError(_, err) => Error::Io(err),
+            err => Error::Format(err.into()),
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for FormatError {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        FormatError::Utf8(err.utf8_error())
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for Error {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        Error::Crypto(err.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_errors_keep_io_exit_code() {
+        let io = || io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(Error::from(csv::Error::from(io())).exit_code(), 3);
+        assert_eq!(Error::from(calamine::Error::Io(io())).exit_code(), 3);
+        let parquet = parquet::errors::ParquetError::from(io());
+        assert_eq!(Error::from(parquet).exit_code(), 3);
+        let parquet = parquet::errors::ParquetError::General("bad footer".into());
+        assert_eq!(Error::from(parquet).exit_code(), 4);
+        assert_eq!(
+            Error::from(calamine::Error::Msg("bad sheet")).exit_code(),
+            4
+        );
+    }
+}
@@ -1,12 +1,25 @@
-//rcli csv -i test.csv -d '|' -o output.json
+//rcli csv convert -i test.csv -d '|' -o output.json
 
 use clap::Parser;
-use rcli::{CmdExecutor, Opts};
+use rcli::{CmdExecutor, Opts, OutputMode};
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() {
     tracing_subscriber::fmt::init();
     let opts: Opts = Opts::parse();
-    opts.cmd.execute().await?;
-    Ok(())
+    let mode = opts.output_format;
+    if let Err(e) = opts.cmd.execute(mode).await {
+        let code = e
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<rcli::Error>())
+            .map_or(1, rcli::Error::exit_code);
+        match mode {
+            OutputMode::Json => eprintln!(
+                "{}",
+                serde_json::json!({ "error": format!("{:#}", e), "exit_code": code })
+            ),
+            OutputMode::Text => eprintln!("Error: {:#}", e),
+        }
+        std::process::exit(code);
+    }
 }
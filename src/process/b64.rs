@@ -1,52 +1,262 @@
-use crate::{utils::get_reader, Base64Format};
-use anyhow::Result;
+use std::io::{self, Read, Write};
+
+use crate::Base64Format;
+use crate::{Error, FormatError, Result};
 use base64::{
-    engine::general_purpose::STANDARD, engine::general_purpose::URL_SAFE_NO_PAD, Engine as _,
+    alphabet,
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD},
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    read::DecoderReader,
+    write::EncoderWriter,
+    Engine,
 };
 
-pub fn process_encode(input: &str, format: Base64Format) -> Result<()> {
-    let mut reader = get_reader(input)?;
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
-    let encoded = match format {
-        Base64Format::Standard => STANDARD.encode(buf),
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.encode(buf),
-    };
+/// Decoding accepts input with or without padding.
+const LENIENT: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
 
-    println!("{}", encoded);
+/// Url-safe input is mapped onto the standard alphabet before decoding.
+const STANDARD_LENIENT: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, LENIENT);
 
+/// Stream `reader` through a base64 encoder into `writer`, in constant memory.
+pub fn process_encode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Base64Format,
+) -> Result<()> {
+    let writer = match format {
+        // RFC 2045 limits lines to 76 characters, separated by CRLF
+        Base64Format::Mime => LineWrapper::new(writer, 76, b"\r\n"),
+        // RFC 7468 wraps the body at 64 characters
+        Base64Format::Pem => LineWrapper::new(writer, 64, b"\n"),
+        _ => LineWrapper::new(writer, usize::MAX, b""),
+    };
+    let writer = match format {
+        Base64Format::Standard | Base64Format::Mime | Base64Format::Pem => {
+            encode_with(reader, writer, &STANDARD)?
+        }
+        Base64Format::StandardNoPad => encode_with(reader, writer, &STANDARD_NO_PAD)?,
+        Base64Format::UrlSafe => encode_with(reader, writer, &URL_SAFE_NO_PAD)?,
+        Base64Format::UrlSafePad => encode_with(reader, writer, &URL_SAFE)?,
+    }
+    .inner;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
     Ok(())
 }
 
-pub fn process_decode(input: &str, format: Base64Format) -> Result<()> {
-    let mut reader = get_reader(input)?;
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
-    let buf = buf.trim();
-    let decoded = match format {
-        Base64Format::Standard => STANDARD.decode(buf)?,
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.decode(buf)?,
+fn encode_with<W: Write, E: Engine>(reader: &mut dyn Read, writer: W, engine: &E) -> Result<W> {
+    let mut encoder = EncoderWriter::new(writer, engine);
+    io::copy(reader, &mut encoder)?;
+    Ok(encoder.finish()?)
+}
+
+/// Stream-decode base64 ignoring whitespace and line breaks, writing the raw
+/// bytes. Without `format` the alphabet is detected from the input.
+pub fn process_decode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Option<Base64Format>,
+) -> Result<()> {
+    let mut normalizer = Normalizer {
+        inner: reader,
+        alphabet: format.map(Base64Format::is_url_safe),
     };
-    let decoded = String::from_utf8(decoded)?;
-    println!("{}", decoded);
+    let mut decoder = DecoderReader::new(&mut normalizer, &STANDARD_LENIENT);
+    io::copy(&mut decoder, writer).map_err(decode_error)?;
+    writer.flush()?;
     Ok(())
 }
 
+/// Malformed input surfaces from the readers as `InvalidData`; report it as a format error.
+fn decode_error(e: io::Error) -> Error {
+    if e.kind() != io::ErrorKind::InvalidData {
+        return Error::Io(e);
+    }
+    let message = e.to_string();
+    match e
+        .into_inner()
+        .map(|inner| inner.downcast::<base64::DecodeError>())
+    {
+        Some(Ok(e)) => FormatError::Base64(*e).into(),
+        _ => Error::invalid_format(message),
+    }
+}
+
+/// Drops whitespace and maps the url-safe alphabet onto the standard one.
+/// `alphabet` is `Some(url_safe)` when known, otherwise it is fixed by the
+/// first alphabet-specific character seen.
+struct Normalizer<R> {
+    inner: R,
+    alphabet: Option<bool>,
+}
+
+impl<R: Read> Read for Normalizer<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut len = 0;
+            for idx in 0..n {
+                let (c, url_safe) = match buf[idx] {
+                    c if c.is_ascii_whitespace() => continue,
+                    b'-' => (b'+', Some(true)),
+                    b'_' => (b'/', Some(true)),
+                    c @ (b'+' | b'/') => (c, Some(false)),
+                    c => (c, None),
+                };
+                if let Some(url_safe) = url_safe {
+                    if *self.alphabet.get_or_insert(url_safe) != url_safe {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "input mixes standard and url-safe base64 alphabets",
+                        ));
+                    }
+                }
+                buf[len] = c;
+                len += 1;
+            }
+            // a chunk of only whitespace must not look like the end of input
+            if len > 0 {
+                return Ok(len);
+            }
+        }
+    }
+}
+
+/// Inserts `separator` after every `width` bytes written.
+struct LineWrapper<W> {
+    inner: W,
+    width: usize,
+    separator: &'static [u8],
+    column: usize,
+}
+
+impl<W> LineWrapper<W> {
+    fn new(inner: W, width: usize, separator: &'static [u8]) -> Self {
+        Self {
+            inner,
+            width,
+            separator,
+            column: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for LineWrapper<W> {
+    // always consumes the whole buffer: EncoderWriter treats short writes as stalls
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.column == self.width {
+                self.inner.write_all(self.separator)?;
+                self.column = 0;
+            }
+            let len = rest.len().min(self.width - self.column);
+            self.inner.write_all(&rest[..len])?;
+            self.column += len;
+            rest = &rest[len..];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn encode_str(data: &[u8], format: Base64Format) -> String {
+        let mut out = Vec::new();
+        process_encode(&mut &data[..], &mut out, format).expect("encode");
+        String::from_utf8(out).expect("base64 is ascii")
+    }
+
+    fn decode_str(data: &str, format: Option<Base64Format>) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        process_decode(&mut data.as_bytes(), &mut out, format)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_process_encode() -> Result<()> {
+        let encoded = encode_str(&std::fs::read("Cargo.toml")?, Base64Format::Standard);
+        assert_eq!(decode_str(&encoded, None)?, std::fs::read("Cargo.toml")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_decode() -> Result<()> {
+        let mut reader = std::fs::File::open("fixture/b64.txt")?;
+        let mut decoded = Vec::new();
+        process_decode(&mut reader, &mut decoded, Some(Base64Format::Standard))?;
+        assert!(decoded.starts_with(b"[package]"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_variants() {
+        let data = [0xfbu8, 0xff, 0xfe, 0x01];
+        assert_eq!(encode_str(&data, Base64Format::Standard), "+//+AQ==\n");
+        assert_eq!(encode_str(&data, Base64Format::StandardNoPad), "+//+AQ\n");
+        assert_eq!(encode_str(&data, Base64Format::UrlSafe), "-__-AQ\n");
+        assert_eq!(encode_str(&data, Base64Format::UrlSafePad), "-__-AQ==\n");
+
+        let data = [0u8; 100];
+        let mime = encode_str(&data, Base64Format::Mime);
+        assert_eq!(
+            mime.trim_end()
+                .split("\r\n")
+                .map(str::len)
+                .collect::<Vec<_>>(),
+            [76, 60]
+        );
+        let pem = encode_str(&data, Base64Format::Pem);
+        assert_eq!(pem.lines().map(str::len).collect::<Vec<_>>(), [64, 64, 8]);
+        let exact = encode_str(&[0u8; 48], Base64Format::Pem);
+        assert_eq!(exact.lines().map(str::len).collect::<Vec<_>>(), [64]);
+    }
+
+    #[test]
+    fn test_decode_lenient() -> Result<()> {
+        let data = vec![0xfbu8, 0xff, 0xfe, 0x01];
+        for input in [
+            "+//+AQ==",
+            "+//+AQ",
+            "-__-AQ==",
+            "-__-AQ",
+            "+//+\r\nAQ==\n",
+            " -__- AQ ",
+        ] {
+            assert_eq!(decode_str(input, None)?, data);
+        }
+        assert_eq!(decode_str("-__-AQ==", Some(Base64Format::UrlSafe))?, data);
+        assert!(decode_str("-__-AQ==", Some(Base64Format::Standard)).is_err());
+        assert!(decode_str("+__-AQ", None).is_err());
+        Ok(())
+    }
+
     #[test]
-    fn test_process_encode() {
-        let input = "Cargo.toml";
-        let format = Base64Format::Standard;
-        assert!(process_encode(input, format).is_ok());
+    fn test_decode_malformed_is_format_error() {
+        for input in ["a$$$", "+__-AQ"] {
+            let err = decode_str(input, None).unwrap_err();
+            assert!(matches!(err, Error::Format(_)), "{:?}", err);
+            assert_eq!(err.exit_code(), 4);
+        }
     }
 
     #[test]
-    fn test_process_decode() {
-        let input = "fixture/b64.txt";
-        let format = Base64Format::Standard;
-        assert!(process_decode(input, format).is_ok());
+    fn test_stream_round_trip() -> Result<()> {
+        let data = (0..=255u8).cycle().take(100_000).collect::<Vec<_>>();
+        for format in [Base64Format::UrlSafe, Base64Format::Mime, Base64Format::Pem] {
+            let encoded = encode_str(&data, format);
+            assert_eq!(decode_str(&encoded, None)?, data);
+        }
+        Ok(())
     }
 }
@@ -1,11 +1,10 @@
-use crate::ChachaFormat;
-use anyhow::Result;
+use crate::{key_bytes, ChachaFormat, CryptoError, Result};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use chacha20poly1305::{
     aead::{Aead, AeadCore, KeyInit, OsRng},
     ChaCha20Poly1305 as OtherChaCha20Poly1305,
 };
-use std::{collections::HashMap, io::Read};
+use std::{collections::HashMap, io::Read, path::PathBuf};
 
 trait ChachaEncrypt {
     fn encrypt(&self, reader: &mut dyn Read) -> Result<(Vec<u8>, Vec<u8>)>;
@@ -19,13 +18,11 @@ impl ChachaEncrypt for ChaCha20Poly1305 {
     fn encrypt(&self, reader: &mut dyn Read) -> Result<(Vec<u8>, Vec<u8>)> {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
-        let cipher = OtherChaCha20Poly1305::new((&self.key[..32]).into());
+        let cipher = OtherChaCha20Poly1305::new(&self.key.into());
         let nonce = OtherChaCha20Poly1305::generate_nonce(&mut OsRng);
-        let ret = cipher.encrypt(&nonce, buf.as_ref());
-        let ret = match ret {
-            Err(_) => return Err(anyhow::anyhow!("Encrypt failed")),
-            Ok(ret) => ret,
-        };
+        let ret = cipher
+            .encrypt(&nonce, buf.as_ref())
+            .map_err(|_| CryptoError::Encrypt)?;
         Ok((ret, nonce[0..12].to_vec()))
     }
 }
@@ -34,13 +31,15 @@ impl ChachaDecrypt for ChaCha20Poly1305 {
     fn decrypt(&self, reader: &mut dyn Read, nonce: &[u8]) -> Result<Vec<u8>> {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
-        let cipher = OtherChaCha20Poly1305::new((&self.key[..32]).into());
+        let cipher = OtherChaCha20Poly1305::new(&self.key.into());
         let buf = URL_SAFE_NO_PAD.decode(&buf)?;
-        let ret = cipher.decrypt((&nonce[..12]).into(), buf.as_ref());
-        let ret = match ret {
-            Err(_) => return Err(anyhow::anyhow!("Decrypt failed")),
-            Ok(ret) => ret,
-        };
+        let nonce: [u8; 12] = nonce.try_into().map_err(|_| CryptoError::NonceLength {
+            expected: 12,
+            actual: nonce.len(),
+        })?;
+        let ret = cipher
+            .decrypt(&nonce.into(), buf.as_ref())
+            .map_err(|_| CryptoError::Decrypt)?;
         Ok(ret)
     }
 }
@@ -55,9 +54,7 @@ impl ChaCha20Poly1305 {
     }
 
     fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
-        let key = key.as_ref();
-        let key = (&key[..32]).try_into()?;
-        Ok(Self::new(key))
+        Ok(Self::new(key_bytes(key.as_ref())?))
     }
 
     fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
@@ -75,9 +72,7 @@ pub fn process_encrypt(
 ) -> Result<(Vec<u8>, Vec<u8>)> {
     let encryptor: Box<dyn ChachaEncrypt> = match format {
         ChachaFormat::ChaCha20Poly1305 => Box::new(ChaCha20Poly1305::try_new(key)?),
-        ChachaFormat::XChaCha20Poly1305
-        | ChachaFormat::ChaCha12Poly1305
-        | ChachaFormat::ChaCha8Poly1305 => todo!(),
+        format => return Err(CryptoError::Unsupported(format.into()).into()),
     };
     let (ret, nonce) = encryptor.encrypt(reader)?;
 
@@ -92,30 +87,31 @@ pub fn process_decrypt(
 ) -> Result<Vec<u8>> {
     let decrypter: Box<dyn ChachaDecrypt> = match format {
         ChachaFormat::ChaCha20Poly1305 => Box::new(ChaCha20Poly1305::try_new(key)?),
-        ChachaFormat::XChaCha20Poly1305
-        | ChachaFormat::ChaCha12Poly1305
-        | ChachaFormat::ChaCha8Poly1305 => todo!(),
+        format => return Err(CryptoError::Unsupported(format.into()).into()),
     };
     let result = decrypter.decrypt(reader, nonce)?;
     Ok(result)
 }
 
-pub fn process_chacha_generate(path: std::path::PathBuf, format: ChachaFormat) -> Result<()> {
+pub fn process_chacha_generate(path: PathBuf, format: ChachaFormat) -> Result<Vec<PathBuf>> {
     let map = match format {
         ChachaFormat::ChaCha20Poly1305 => ChaCha20Poly1305::generate()?,
-        ChachaFormat::XChaCha20Poly1305
-        | ChachaFormat::ChaCha12Poly1305
-        | ChachaFormat::ChaCha8Poly1305 => todo!(),
+        format => return Err(CryptoError::Unsupported(format.into()).into()),
     };
+    let mut files = Vec::new();
     for (filename, key) in map {
-        std::fs::write(path.join(filename), key)?;
+        let file = path.join(filename);
+        std::fs::write(&file, key)?;
+        files.push(file);
     }
-    Ok(())
+    files.sort();
+    Ok(files)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{Error, KeyError};
 
     #[test]
     fn test_process_encrypt_decrypt() -> Result<()> {
@@ -136,4 +132,38 @@ mod tests {
         assert_eq!("hello", decrypt_ret);
         Ok(())
     }
+
+    #[test]
+    fn test_invalid_key_nonce_and_format() {
+        let key: &[u8] = include_bytes!("../../fixture/ChaCha20Poly1305.txt");
+        let err = process_encrypt(
+            &mut "hello".as_bytes(),
+            &key[..16],
+            ChachaFormat::ChaCha20Poly1305,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Key(KeyError::InvalidLength {
+                expected: 32,
+                actual: 16
+            })
+        ));
+
+        let err = process_decrypt(
+            &mut "AAAA".as_bytes(),
+            key,
+            &[0; 8],
+            ChachaFormat::ChaCha20Poly1305,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Crypto(CryptoError::NonceLength { .. })
+        ));
+
+        let err = process_encrypt(&mut "hello".as_bytes(), key, ChachaFormat::ChaCha8Poly1305)
+            .unwrap_err();
+        assert!(matches!(err, Error::Crypto(CryptoError::Unsupported(_))));
+    }
 }
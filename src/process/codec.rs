@@ -0,0 +1,384 @@
+use std::io::{ErrorKind, Read, Write};
+
+use crate::{Error, Result};
+use data_encoding::{
+    BASE32, BASE32HEX, BASE32HEX_NOPAD, BASE32_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE,
+};
+use percent_encoding::{percent_decode, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::cli::Codec;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// RFC 3986 unreserved characters are left as is.
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// Longest named HTML entity, `&CounterClockwiseContourIntegral;`.
+const MAX_ENTITY_LEN: usize = 33;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Encode,
+    Decode,
+}
+
+/// Encode `reader` with `codec`, streaming in fixed-size chunks. Base58 is the
+/// exception: it is one big number, so the whole input is buffered.
+pub fn process_codec_encode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    codec: Codec,
+) -> Result<()> {
+    transcode(reader, writer, codec, Direction::Encode, CHUNK_SIZE)
+}
+
+/// Decode `input` and write the raw bytes, so binary data survives. Whitespace
+/// in the input is ignored, except for HTML where it is part of the text.
+pub fn process_codec_decode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    codec: Codec,
+) -> Result<()> {
+    transcode(reader, writer, codec, Direction::Decode, CHUNK_SIZE)
+}
+
+fn transcode(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    codec: Codec,
+    direction: Direction,
+    chunk_size: usize,
+) -> Result<()> {
+    // HTML escaping keeps whitespace as is; every other encoding drops or escapes it
+    let text_safe = codec != Codec::Html;
+    let skip_whitespace = direction == Direction::Decode && text_safe;
+    let mut chunk = vec![0u8; chunk_size];
+    let mut pending = Vec::new();
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if skip_whitespace {
+            pending.extend(chunk[..n].iter().filter(|b| !b.is_ascii_whitespace()));
+        } else {
+            pending.extend_from_slice(&chunk[..n]);
+        }
+        let ready = split_point(codec, direction, &pending);
+        writer.write_all(&convert(codec, direction, &pending[..ready])?)?;
+        pending.drain(..ready);
+    }
+    writer.write_all(&convert(codec, direction, &pending)?)?;
+    if direction == Direction::Encode && text_safe {
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Length of the prefix of `buf` that converts the same now as it would with more input.
+fn split_point(codec: Codec, direction: Direction, buf: &[u8]) -> usize {
+    let len = buf.len();
+    match (direction, codec) {
+        (_, Codec::Base58) => 0,
+        (Direction::Encode, Codec::Hex | Codec::Url | Codec::Html) => len,
+        (Direction::Encode, Codec::Base32 | Codec::Base32Hex) => len - len % 5,
+        (Direction::Encode, Codec::Base85 | Codec::Z85) => len - len % 4,
+        (Direction::Decode, Codec::Hex) => len - len % 2,
+        (Direction::Decode, Codec::Base32 | Codec::Base32Hex) => len - len % 8,
+        (Direction::Decode, Codec::Z85) => len - len % 5,
+        (Direction::Decode, Codec::Base85) => {
+            let mut idx = 0;
+            while idx < len {
+                if buf[idx] == b'z' {
+                    idx += 1;
+                } else if idx + 5 <= len {
+                    idx += 5;
+                } else {
+                    break;
+                }
+            }
+            idx
+        }
+        (Direction::Decode, Codec::Url) => match buf.iter().rposition(|&b| b == b'%') {
+            Some(idx) if len - idx < 3 => idx,
+            _ => len,
+        },
+        (Direction::Decode, Codec::Html) => {
+            let valid = match std::str::from_utf8(buf) {
+                Ok(_) => len,
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                // invalid data is reported by convert
+                Err(_) => len,
+            };
+            match buf[..valid].iter().rposition(|&b| b == b'&') {
+                Some(idx) if !buf[idx..valid].contains(&b';') && valid - idx < MAX_ENTITY_LEN => {
+                    idx
+                }
+                _ => valid,
+            }
+        }
+    }
+}
+
+fn convert(codec: Codec, direction: Direction, data: &[u8]) -> Result<Vec<u8>> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let converted = match direction {
+        Direction::Encode => match codec {
+            Codec::Hex => HEXLOWER.encode(data).into_bytes(),
+            Codec::Base32 => BASE32.encode(data).into_bytes(),
+            Codec::Base32Hex => BASE32HEX.encode(data).into_bytes(),
+            Codec::Base58 => bs58::encode(data).into_vec(),
+            Codec::Base85 => ascii85_encode(data),
+            Codec::Z85 => z85_encode(data)?,
+            Codec::Url => percent_encode(data, URL_ENCODE_SET)
+                .to_string()
+                .into_bytes(),
+            Codec::Html => html_encode(data),
+        },
+        Direction::Decode => match codec {
+            Codec::Hex => HEXLOWER_PERMISSIVE.decode(data)?,
+            Codec::Base32 => BASE32_NOPAD.decode(&base32_normalize(data))?,
+            Codec::Base32Hex => BASE32HEX_NOPAD.decode(&base32_normalize(data))?,
+            Codec::Base58 => bs58::decode(data).into_vec()?,
+            Codec::Base85 => ascii85_decode(data)?,
+            Codec::Z85 => z85_decode(data)?,
+            Codec::Url => percent_decode(data).collect(),
+            Codec::Html => {
+                let text = std::str::from_utf8(data)?;
+                html_escape::decode_html_entities(text)
+                    .into_owned()
+                    .into_bytes()
+            }
+        },
+    };
+    Ok(converted)
+}
+
+/// Accept lowercase and unpadded base32.
+fn base32_normalize(data: &[u8]) -> Vec<u8> {
+    data.iter()
+        .filter(|&&b| b != b'=')
+        .map(u8::to_ascii_uppercase)
+        .collect()
+}
+
+fn html_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for &b in data {
+        match b {
+            b'&' => out.extend_from_slice(b"&amp;"),
+            b'<' => out.extend_from_slice(b"&lt;"),
+            b'>' => out.extend_from_slice(b"&gt;"),
+            b'"' => out.extend_from_slice(b"&quot;"),
+            b'\'' => out.extend_from_slice(b"&#x27;"),
+            b => out.push(b),
+        }
+    }
+    out
+}
+
+fn base85_digits(mut value: u32) -> [u8; 5] {
+    let mut digits = [0u8; 5];
+    for digit in digits.iter_mut().rev() {
+        *digit = (value % 85) as u8;
+        value /= 85;
+    }
+    digits
+}
+
+fn base85_value(digits: impl Iterator<Item = Result<u8>>) -> Result<u32> {
+    let mut value = 0u64;
+    for digit in digits {
+        value = value * 85 + digit? as u64;
+    }
+    u32::try_from(value).map_err(|_| Error::invalid_format("Base85 group out of range"))
+}
+
+/// Ascii85 as used by btoa and PostScript, without the `<~ ~>` delimiters.
+fn ascii85_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 4 * 5 + 5);
+    for group in data.chunks(4) {
+        if group == [0, 0, 0, 0] {
+            out.push(b'z');
+            continue;
+        }
+        let mut bytes = [0u8; 4];
+        bytes[..group.len()].copy_from_slice(group);
+        let digits = base85_digits(u32::from_be_bytes(bytes));
+        out.extend(digits[..group.len() + 1].iter().map(|d| d + b'!'));
+    }
+    out
+}
+
+fn ascii85_decode(data: &[u8]) -> Result<Vec<u8>> {
+    let digit = |c: u8| match c {
+        b'!'..=b'u' => Ok(c - b'!'),
+        _ => Err(Error::invalid_format(format!(
+            "Invalid base85 character {:?}",
+            c as char
+        ))),
+    };
+    let mut out = Vec::with_capacity(data.len() / 5 * 4 + 4);
+    let mut idx = 0;
+    while idx < data.len() {
+        if data[idx] == b'z' {
+            out.extend_from_slice(&[0; 4]);
+            idx += 1;
+            continue;
+        }
+        let group = &data[idx..data.len().min(idx + 5)];
+        if group.len() == 1 {
+            return Err(Error::invalid_format("Truncated base85 input"));
+        }
+        // a short final group is padded with the highest digit
+        let padded = group.iter().copied().chain(std::iter::repeat(b'u'));
+        let value = base85_value(padded.take(5).map(digit))?;
+        out.extend_from_slice(&value.to_be_bytes()[..group.len() - 1]);
+        idx += group.len();
+    }
+    Ok(out)
+}
+
+/// ZeroMQ Z85 (RFC 32), which only encodes whole 4-byte groups.
+fn z85_encode(data: &[u8]) -> Result<Vec<u8>> {
+    if !data.len().is_multiple_of(4) {
+        return Err(Error::invalid_format(
+            "Z85 input length must be a multiple of 4",
+        ));
+    }
+    Ok(data
+        .chunks(4)
+        .flat_map(|group| {
+            let value = u32::from_be_bytes(group.try_into().expect("4-byte group"));
+            base85_digits(value).map(|d| Z85_ALPHABET[d as usize])
+        })
+        .collect())
+}
+
+fn z85_decode(data: &[u8]) -> Result<Vec<u8>> {
+    if !data.len().is_multiple_of(5) {
+        return Err(Error::invalid_format(
+            "Z85 input length must be a multiple of 5",
+        ));
+    }
+    let digit = |c: &u8| match Z85_ALPHABET.iter().position(|a| a == c) {
+        Some(d) => Ok(d as u8),
+        None => Err(Error::invalid_format(format!(
+            "Invalid Z85 character {:?}",
+            *c as char
+        ))),
+    };
+    let mut out = Vec::with_capacity(data.len() / 5 * 4);
+    for group in data.chunks(5) {
+        out.extend_from_slice(&base85_value(group.iter().map(digit))?.to_be_bytes());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODECS: [Codec; 8] = [
+        Codec::Hex,
+        Codec::Base32,
+        Codec::Base32Hex,
+        Codec::Base58,
+        Codec::Base85,
+        Codec::Z85,
+        Codec::Url,
+        Codec::Html,
+    ];
+
+    fn run(data: &[u8], codec: Codec, direction: Direction, chunk_size: usize) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        transcode(data, &mut out, codec, direction, chunk_size)?;
+        Ok(out)
+    }
+
+    fn encode(data: &[u8], codec: Codec) -> String {
+        let encoded = run(data, codec, Direction::Encode, CHUNK_SIZE).expect("encode");
+        String::from_utf8(encoded)
+            .expect("text")
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_encode_vectors() {
+        assert_eq!(encode(b"foobar", Codec::Hex), "666f6f626172");
+        assert_eq!(encode(b"foobar", Codec::Base32), "MZXW6YTBOI======");
+        assert_eq!(encode(b"foobar", Codec::Base32Hex), "CPNMUOJ1E8======");
+        assert_eq!(encode(b"Hello World!", Codec::Base58), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(encode(b"Man is", Codec::Base85), "9jqo^Bla");
+        assert_eq!(encode(&[0, 0, 0, 0, 1], Codec::Base85), "z!<");
+        assert_eq!(
+            encode(
+                &[0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b],
+                Codec::Z85
+            ),
+            "HelloWorld"
+        );
+        assert_eq!(
+            encode("a b&c/é~".as_bytes(), Codec::Url),
+            "a%20b%26c%2F%C3%A9~"
+        );
+        assert_eq!(
+            encode(b"<a href='x'>", Codec::Html),
+            "&lt;a href=&#x27;x&#x27;&gt;"
+        );
+    }
+
+    #[test]
+    fn test_decode_lenient() -> Result<()> {
+        assert_eq!(run(b"66 6F\n6f", Codec::Hex, Direction::Decode, 3)?, b"foo");
+        assert_eq!(
+            run(b"mzxw6ytboi", Codec::Base32, Direction::Decode, 4)?,
+            b"foobar"
+        );
+        assert_eq!(
+            run(
+                b"a&lt;b &amp;&eacute; & c",
+                Codec::Html,
+                Direction::Decode,
+                2
+            )?,
+            "a<b &é & c".as_bytes()
+        );
+        assert!(run(b"abc", Codec::Z85, Direction::Decode, 16).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_streaming() -> Result<()> {
+        let mut data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice("<é & ü>".as_bytes());
+        for codec in CODECS {
+            let data = match codec {
+                Codec::Z85 => &data[..data.len() / 4 * 4],
+                // HTML entities only make sense for text
+                Codec::Html => "x < y & ü > \"z\"".as_bytes(),
+                _ => &data[..],
+            };
+            for chunk_size in [1, 3, 7, 4096] {
+                let encoded = run(data, codec, Direction::Encode, chunk_size)?;
+                let decoded = run(&encoded, codec, Direction::Decode, chunk_size)?;
+                assert_eq!(decoded, data, "{:?} with chunk size {}", codec, chunk_size);
+            }
+        }
+        Ok(())
+    }
+}
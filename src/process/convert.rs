@@ -0,0 +1,315 @@
+use crate::{Error, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::{cli::DataFormat, utils::get_content};
+
+pub fn process_convert(
+    input: &str,
+    from: Option<DataFormat>,
+    to: DataFormat,
+    pretty: bool,
+    sort_keys: bool,
+) -> Result<String> {
+    let mut docs = read_input(input, from)?;
+    if sort_keys {
+        docs = docs.into_iter().map(sort_value).collect();
+    }
+    write_documents(&docs, to, pretty)
+}
+
+/// Read `input` and parse every document in it, detecting the format unless `from` is given.
+pub(crate) fn read_input(input: &str, from: Option<DataFormat>) -> Result<Vec<Value>> {
+    let content = String::from_utf8(get_content(input)?)?;
+    let format = match from {
+        Some(format) => format,
+        None => detect_format(input, &content)?,
+    };
+    read_documents(&content, format)
+}
+
+pub(crate) fn read_documents(content: &str, format: DataFormat) -> Result<Vec<Value>> {
+    let docs = match format {
+        DataFormat::Json => vec![serde_json::from_str(content)?],
+        DataFormat::Yaml => {
+            let mut docs = Vec::new();
+            for doc in serde_yaml::Deserializer::from_str(content) {
+                docs.push(Value::deserialize(doc)?);
+            }
+            docs
+        }
+        DataFormat::Toml => vec![toml::from_str(content)?],
+        DataFormat::Ndjson => content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?,
+        DataFormat::Json5 => vec![json5::from_str(content)?],
+        DataFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(content.as_bytes());
+            let headers = reader.headers()?.clone();
+            let mut rows = Vec::new();
+            for record in reader.records() {
+                rows.push(headers.iter().zip(record?.iter()).collect::<Value>());
+            }
+            vec![Value::Array(rows)]
+        }
+    };
+    Ok(docs)
+}
+
+pub(crate) fn write_documents(docs: &[Value], format: DataFormat, pretty: bool) -> Result<String> {
+    let single = match docs {
+        [doc] => doc.clone(),
+        docs => Value::Array(docs.to_vec()),
+    };
+    let content = match format {
+        DataFormat::Json if pretty => serde_json::to_string_pretty(&single)?,
+        DataFormat::Json => serde_json::to_string(&single)?,
+        DataFormat::Yaml => {
+            let mut content = String::new();
+            for (idx, doc) in docs.iter().enumerate() {
+                if idx > 0 {
+                    content.push_str("---\n");
+                }
+                content.push_str(&serde_yaml::to_string(doc)?);
+            }
+            content
+        }
+        DataFormat::Toml => {
+            check_toml(&single)?;
+            if pretty {
+                toml::to_string_pretty(&single)?
+            } else {
+                toml::to_string(&single)?
+            }
+        }
+        DataFormat::Ndjson => {
+            let lines = match docs {
+                [Value::Array(items)] => items.as_slice(),
+                docs => docs,
+            };
+            let mut content = String::new();
+            for line in lines {
+                content.push_str(&serde_json::to_string(line)?);
+                content.push('\n');
+            }
+            content
+        }
+        DataFormat::Json5 => json5::to_string(&single)?,
+        DataFormat::Csv => write_csv(&single)?,
+    };
+    Ok(content)
+}
+
+/// TOML documents are tables and have no null, which the serializer reports
+/// only as "unsupported rust type".
+fn check_toml(value: &Value) -> Result<()> {
+    if !value.is_object() {
+        return Err(Error::invalid_format(format!(
+            "TOML output requires a top-level table, got {}",
+            kind(value)
+        )));
+    }
+    fn find_null(value: &Value, path: String) -> Option<String> {
+        match value {
+            Value::Null => Some(path),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .find_map(|(idx, item)| find_null(item, format!("{}/{}", path, idx))),
+            Value::Object(map) => map
+                .iter()
+                .find_map(|(key, item)| find_null(item, format!("{}/{}", path, key))),
+            _ => None,
+        }
+    }
+    match find_null(value, String::new()) {
+        Some(path) => Err(Error::invalid_format(format!(
+            "TOML has no null value, found one at {}",
+            path
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "a table",
+    }
+}
+
+fn write_csv(value: &Value) -> Result<String> {
+    let rows = match value {
+        Value::Array(items) => items.as_slice(),
+        value => std::slice::from_ref(value),
+    };
+    let mut headers: Vec<&str> = Vec::new();
+    for row in rows {
+        let Value::Object(map) = row else {
+            return Err(Error::invalid_format(
+                "CSV output requires a list of objects",
+            ));
+        };
+        for key in map.keys() {
+            if !headers.contains(&key.as_str()) {
+                headers.push(key);
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&headers)?;
+    for row in rows {
+        writer.write_record(headers.iter().map(|h| match row.get(h) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+        }))?;
+    }
+    let buf = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8(buf)?)
+}
+
+/// Guess the format from the file extension, falling back to sniffing the content.
+pub(crate) fn detect_format(input: &str, content: &str) -> Result<DataFormat> {
+    let extension = Path::new(input)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    if let Some(format) = extension.and_then(|ext| ext.parse().ok()) {
+        return Ok(format);
+    }
+
+    if serde_json::from_str::<Value>(content).is_ok() {
+        return Ok(DataFormat::Json);
+    }
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    if lines.clone().next().is_some()
+        && lines.all(|line| serde_json::from_str::<Value>(line).is_ok())
+    {
+        return Ok(DataFormat::Ndjson);
+    }
+    if toml::from_str::<Value>(content).is_ok() {
+        return Ok(DataFormat::Toml);
+    }
+    if json5::from_str::<Value>(content).is_ok() {
+        return Ok(DataFormat::Json5);
+    }
+    if read_documents(content, DataFormat::Yaml).is_ok() {
+        return Ok(DataFormat::Yaml);
+    }
+    Err(Error::invalid_format("Unable to detect input format"))
+}
+
+fn sort_value(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_value(v)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_value).collect()),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_detect_format() -> Result<()> {
+        assert!(matches!(detect_format("a.yml", "")?, DataFormat::Yaml));
+        assert!(matches!(
+            detect_format("-", r#"{"a": 1}"#)?,
+            DataFormat::Json
+        ));
+        assert!(matches!(
+            detect_format("-", "{\"a\": 1}\n{\"a\": 2}\n")?,
+            DataFormat::Ndjson
+        ));
+        assert!(matches!(
+            detect_format("-", "[a]\nb = 1\n")?,
+            DataFormat::Toml
+        ));
+        assert!(matches!(detect_format("-", "{a: 1,}")?, DataFormat::Json5));
+        assert!(matches!(
+            detect_format("-", "a:\n  - b\n")?,
+            DataFormat::Yaml
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_yaml_multi_document() -> Result<()> {
+        let docs = read_documents("a: 1\n---\na: 2\n", DataFormat::Yaml)?;
+        assert_eq!(docs, vec![json!({"a": 1}), json!({"a": 2})]);
+        assert_eq!(
+            write_documents(&docs, DataFormat::Ndjson, false)?,
+            "{\"a\":1}\n{\"a\":2}\n"
+        );
+        assert_eq!(
+            write_documents(&docs, DataFormat::Yaml, false)?,
+            "a: 1\n---\na: 2\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_round_trip() -> Result<()> {
+        let doc = json!({"server": {"port": 8080, "host": "localhost"}, "debug": true});
+        for format in [
+            DataFormat::Json,
+            DataFormat::Yaml,
+            DataFormat::Toml,
+            DataFormat::Json5,
+        ] {
+            let content = write_documents(std::slice::from_ref(&doc), format, true)?;
+            assert_eq!(read_documents(&content, format)?, vec![doc.clone()]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_toml_output_errors() {
+        let err = |doc: Value| {
+            write_documents(&[doc], DataFormat::Toml, false)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(err(Value::Null).contains("requires a top-level table, got null"));
+        assert!(err(json!([1, 2])).contains("requires a top-level table, got an array"));
+        assert!(err(json!({"a": {"b": [1, null]}})).contains("at /a/b/1"));
+    }
+
+    #[test]
+    fn test_csv_documents() -> Result<()> {
+        let docs = read_documents("a,b\n1,x\n2,\n", DataFormat::Csv)?;
+        assert_eq!(
+            docs,
+            vec![json!([{"a": "1", "b": "x"}, {"a": "2", "b": ""}])]
+        );
+        let content = write_documents(&[json!([{"a": 1}, {"b": null}])], DataFormat::Csv, true)?;
+        assert_eq!(content, "a,b\n1,\n,\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let doc = json!({"b": 1, "a": {"d": 1, "c": 2}});
+        let sorted = serde_json::to_string(&sort_value(doc)).unwrap();
+        assert_eq!(sorted, r#"{"a":{"c":2,"d":1},"b":1}"#);
+    }
+}
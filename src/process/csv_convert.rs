@@ -1,39 +1,295 @@
-use anyhow::Result;
-use csv::Reader;
-use serde::{Deserialize, Serialize};
+use crate::{Error, Result};
+use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use calamine::{open_workbook_auto, Reader as _};
+use csv::{Reader, ReaderBuilder, StringRecord};
+use parquet::{
+    arrow::ArrowWriter,
+    file::reader::{FileReader, SerializedFileReader},
+    record::Field as ParquetField,
+};
 use serde_json::Value;
-use std::fs;
-
-use crate::cli::OutputFormat;
-
-//Name,Position,DOB,Nationality,Kit Number
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-struct Player {
-    name: String,
-    position: String,
-    #[serde(rename = "DOB")]
-    dob: String,
-    nationality: String,
-    #[serde(rename = "Kit Number")]
-    kit: u8,
-}
-
-pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<()> {
-    let mut reader = Reader::from_path(input)?;
-    let mut ret = Vec::with_capacity(256);
-    let herders = reader.headers()?.clone();
-
-    for result in reader.records() {
-        let record = result?;
-        let json_value = herders.iter().zip(record.iter()).collect::<Value>();
-        ret.push(json_value);
-    }
-    let content = match format {
-        OutputFormat::Json => serde_json::to_string_pretty(&ret)?,
-        OutputFormat::Yaml => serde_yaml::to_string(&ret)?,
+use std::{
+    fs::File,
+    io::{Read, Write},
+    sync::Arc,
+};
+
+use crate::{
+    cli::{InputFormat, OutputFormat},
+    utils::get_reader,
+    ColumnType,
+};
+
+/// How `rcli csv` reads its inputs.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOptions<'a> {
+    /// Detected from each input's file extension when `None`
+    pub format: Option<InputFormat>,
+    /// Worksheet name or zero-based index for xlsx input
+    pub sheet: Option<&'a str>,
+    pub delimiter: u8,
+}
+
+impl Default for ReadOptions<'_> {
+    fn default() -> Self {
+        Self {
+            format: None,
+            sheet: None,
+            delimiter: b',',
+        }
+    }
+}
+
+/// A header row and its records; csv input is read lazily.
+pub(crate) struct Table {
+    pub(crate) headers: StringRecord,
+    pub(crate) records: Box<dyn Iterator<Item = Result<StringRecord>>>,
+}
+
+impl Table {
+    pub(crate) fn from_csv<R: Read + 'static>(mut reader: Reader<R>) -> Result<Self> {
+        let headers = reader.headers()?.clone();
+        let records = reader.into_records().map(|r| r.map_err(Error::from));
+        Ok(Self {
+            headers,
+            records: Box::new(records),
+        })
+    }
+
+    fn from_rows(headers: StringRecord, rows: Vec<StringRecord>) -> Self {
+        Self {
+            headers,
+            records: Box::new(rows.into_iter().map(Ok)),
+        }
+    }
+}
+
+/// Rows produced by `rcli csv`, keyed by the output headers.
+#[derive(Debug, Default)]
+pub struct Records {
+    pub headers: Vec<String>,
+    pub rows: Vec<Value>,
+}
+
+pub fn process_csv(input: &str, options: &ReadOptions) -> Result<Records> {
+    let table = read_table(input, options)?;
+    let headers = table.headers;
+    let mut rows = Vec::new();
+
+    for record in table.records {
+        let json_value = headers.iter().zip(record?.iter()).collect::<Value>();
+        rows.push(json_value);
+    }
+    Ok(Records {
+        headers: headers.iter().map(String::from).collect(),
+        rows,
+    })
+}
+
+/// Serialize records with the given `OutputFormat` into `writer`.
+pub fn write_records(
+    records: &Records,
+    writer: &mut (dyn Write + Send),
+    format: OutputFormat,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json => serde_json::to_writer_pretty(&mut *writer, &records.rows)?,
+        OutputFormat::Yaml => serde_yaml::to_writer(&mut *writer, &records.rows)?,
+        OutputFormat::Parquet => write_parquet(records, writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Build the csv reader shared by every `rcli csv` subcommand.
+pub(crate) fn csv_reader(input: &str, delimiter: u8) -> Result<Reader<Box<dyn Read>>> {
+    let reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(get_reader(input)?);
+    Ok(reader)
+}
+
+/// Read any supported input into a header row and its records.
+pub(crate) fn read_table(input: &str, options: &ReadOptions) -> Result<Table> {
+    let format = options.format.unwrap_or_else(|| InputFormat::detect(input));
+    match format {
+        InputFormat::Csv => Table::from_csv(csv_reader(input, options.delimiter)?),
+        InputFormat::Xlsx => read_xlsx(input, options.sheet),
+        InputFormat::Parquet => read_parquet(input),
+    }
+}
+
+fn read_xlsx(input: &str, sheet: Option<&str>) -> Result<Table> {
+    let mut workbook = open_workbook_auto(input)?;
+    let range = match sheet {
+        Some(sheet) => match sheet.parse::<usize>() {
+            Ok(idx) => workbook
+                .worksheet_range_at(idx)
+                .ok_or_else(|| Error::invalid_input(format!("Sheet index {} not found", idx)))??,
+            Err(_) => workbook.worksheet_range(sheet)?,
+        },
+        None => workbook
+            .worksheet_range_at(0)
+            .ok_or_else(|| Error::invalid_input("Workbook has no sheets"))??,
     };
-    //let json = serde_json::to_string_pretty(&ret)?;
-    fs::write(output, content)?;
+
+    let mut rows = range.rows().map(|row| {
+        row.iter()
+            .map(|cell| cell.to_string())
+            .collect::<StringRecord>()
+    });
+    let headers = rows.next().unwrap_or_default();
+    Ok(Table::from_rows(headers, rows.collect()))
+}
+
+fn read_parquet(input: &str) -> Result<Table> {
+    let reader = SerializedFileReader::new(File::open(input)?)?;
+    let headers = reader
+        .metadata()
+        .file_metadata()
+        .schema()
+        .get_fields()
+        .iter()
+        .map(|field| field.name())
+        .collect::<StringRecord>();
+
+    let mut records = Vec::new();
+    for row in reader.get_row_iter(None)? {
+        let record = row?
+            .get_column_iter()
+            .map(|(_, field)| match field {
+                ParquetField::Null => String::new(),
+                ParquetField::Str(s) => s.clone(),
+                field => field.to_string(),
+            })
+            .collect::<StringRecord>();
+        records.push(record);
+    }
+    Ok(Table::from_rows(headers, records))
+}
+
+fn write_parquet(records: &Records, writer: &mut (dyn Write + Send)) -> Result<()> {
+    let headers = &records.headers;
+    let mut fields = Vec::with_capacity(headers.len());
+    let mut columns = Vec::with_capacity(headers.len());
+    for header in headers {
+        let values = records
+            .rows
+            .iter()
+            .map(|record| match record.get(header) {
+                None | Some(Value::Null) => None,
+                Some(Value::String(s)) if s.is_empty() => None,
+                Some(Value::String(s)) => Some(s.clone()),
+                Some(v) => Some(v.to_string()),
+            })
+            .collect::<Vec<_>>();
+        let (data_type, column) = infer_column(values);
+        fields.push(Field::new(header.as_str(), data_type, true));
+        columns.push(column);
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    let mut writer = ArrowWriter::try_new(writer, schema.clone(), None)?;
+    // a header-only input still gets a file with its schema
+    if !records.rows.is_empty() {
+        writer.write(&RecordBatch::try_new(schema, columns)?)?;
+    }
+    writer.close()?;
     Ok(())
 }
+
+fn infer_column(values: Vec<Option<String>>) -> (DataType, ArrayRef) {
+    let all = |column_type: ColumnType| values.iter().flatten().all(|v| column_type.check(v));
+    if values.iter().any(Option::is_some) {
+        if all(ColumnType::Integer) {
+            let array = values
+                .iter()
+                .map(|v| v.as_ref().and_then(|v| v.parse().ok()));
+            return (DataType::Int64, Arc::new(array.collect::<Int64Array>()));
+        }
+        if all(ColumnType::Float) {
+            let array = values
+                .iter()
+                .map(|v| v.as_ref().and_then(|v| v.parse().ok()));
+            return (DataType::Float64, Arc::new(array.collect::<Float64Array>()));
+        }
+        if all(ColumnType::Boolean) {
+            let array = values.iter().map(|v| {
+                v.as_ref()
+                    .map(|v| matches!(v.to_lowercase().as_str(), "true" | "yes" | "1"))
+            });
+            return (DataType::Boolean, Arc::new(array.collect::<BooleanArray>()));
+        }
+    }
+    (DataType::Utf8, Arc::new(StringArray::from(values)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(input: &str, options: &ReadOptions) -> Result<(StringRecord, Vec<StringRecord>)> {
+        let table = read_table(input, options)?;
+        Ok((table.headers, table.records.collect::<Result<_>>()?))
+    }
+
+    #[test]
+    fn test_read_xlsx_sheets() -> Result<()> {
+        let input = "fixture/juventus.xlsx";
+        let (headers, records) = read_all(input, &ReadOptions::default())?;
+        assert_eq!(headers, vec!["Name", "Position", "Kit Number"]);
+        assert_eq!(records.len(), 3);
+        assert_eq!(&records[1][2], "37");
+
+        let sheet = |sheet| ReadOptions {
+            sheet: Some(sheet),
+            ..Default::default()
+        };
+        let (by_name, _) = read_all(input, &sheet("Clubs"))?;
+        let (by_index, _) = read_all(input, &sheet("1"))?;
+        assert_eq!(by_name, vec!["Club", "Founded"]);
+        assert_eq!(by_name, by_index);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parquet_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let output = dir.path().join("juventus.parquet");
+        let output_str = output.to_string_lossy().to_string();
+        let records = process_csv("assets/juventus.csv", &ReadOptions::default())?;
+        write_records(&records, &mut File::create(&output)?, OutputFormat::Parquet)?;
+
+        let reader = SerializedFileReader::new(File::open(&output)?)?;
+        let schema = reader.metadata().file_metadata().schema_descr_ptr();
+        let kit = schema.columns().iter().find(|c| c.name() == "Kit Number");
+        assert_eq!(
+            kit.map(|c| c.physical_type()),
+            Some(parquet::basic::Type::INT64)
+        );
+
+        let (headers, records) = read_all(&output_str, &ReadOptions::default())?;
+        assert_eq!(headers.len(), 5);
+        assert_eq!(records.len(), 27);
+        let name = headers.iter().position(|h| h == "Name").unwrap();
+        assert_eq!(&records[0][name], "Wojciech Szczesny");
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_only_parquet() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("empty.csv");
+        let output = dir.path().join("empty.parquet");
+        std::fs::write(&input, "id,name\n")?;
+        let records = process_csv(&input.to_string_lossy(), &ReadOptions::default())?;
+        assert!(records.rows.is_empty());
+        write_records(&records, &mut File::create(&output)?, OutputFormat::Parquet)?;
+
+        let (headers, records) = read_all(&output.to_string_lossy(), &ReadOptions::default())?;
+        assert_eq!(headers, vec!["id", "name"]);
+        assert!(records.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,388 @@
+use crate::{Error, Result};
+use csv::StringRecord;
+use serde_json::{Map, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+
+use super::csv_convert::{read_table, ReadOptions, Records, Table};
+use crate::cli::JoinKind;
+
+struct JoinLayout {
+    left_headers: StringRecord,
+    right_headers: Vec<(usize, String)>,
+    left_key: usize,
+    right_key: usize,
+}
+
+impl JoinLayout {
+    fn new(left_headers: StringRecord, right_headers: &StringRecord, on: &str) -> Result<Self> {
+        let position = |headers: &StringRecord| {
+            headers
+                .iter()
+                .position(|h| h == on)
+                .ok_or_else(|| Error::invalid_input(format!("Join column {:?} not found", on)))
+        };
+        let left_key = position(&left_headers)?;
+        let right_key = position(right_headers)?;
+        let mut taken = left_headers
+            .iter()
+            .chain(right_headers.iter())
+            .map(String::from)
+            .collect::<HashSet<_>>();
+        let right_headers = right_headers
+            .iter()
+            .enumerate()
+            .filter(|&(idx, _)| idx != right_key)
+            .map(|(idx, name)| {
+                if !left_headers.iter().any(|h| h == name) {
+                    return (idx, name.to_string());
+                }
+                // `name_right`, or `name_right2`, ... when that is a column too
+                let mut renamed = format!("{}_right", name);
+                let mut n = 2;
+                while taken.contains(&renamed) {
+                    renamed = format!("{}_right{}", name, n);
+                    n += 1;
+                }
+                taken.insert(renamed.clone());
+                (idx, renamed)
+            })
+            .collect();
+        Ok(Self {
+            left_headers,
+            right_headers,
+            left_key,
+            right_key,
+        })
+    }
+
+    fn headers(&self) -> Vec<String> {
+        let left = self.left_headers.iter().map(String::from);
+        left.chain(self.right_headers.iter().map(|(_, name)| name.clone()))
+            .collect()
+    }
+
+    fn merge(&self, left: Option<&StringRecord>, right: Option<&StringRecord>) -> Value {
+        let field = |record: Option<&StringRecord>, idx: usize| {
+            record
+                .and_then(|r| r.get(idx))
+                .map_or(Value::Null, |v| Value::String(v.to_string()))
+        };
+        let mut row = Map::new();
+        for (idx, name) in self.left_headers.iter().enumerate() {
+            let value = match (idx == self.left_key, left) {
+                (true, None) => field(right, self.right_key),
+                _ => field(left, idx),
+            };
+            row.insert(name.to_string(), value);
+        }
+        for (idx, name) in &self.right_headers {
+            row.insert(name.clone(), field(right, *idx));
+        }
+        Value::Object(row)
+    }
+}
+
+pub fn process_csv_join(
+    left: &str,
+    right: &str,
+    on: &str,
+    kind: JoinKind,
+    options: &ReadOptions,
+) -> Result<Records> {
+    // build the hash table from the smaller input and stream the larger one
+    let build_left = input_size(left) < input_size(right);
+    let left = read_table(left, options)?;
+    let right = read_table(right, options)?;
+    join(left, right, on, kind, build_left)
+}
+
+pub fn process_csv_concat(inputs: &[String], options: &ReadOptions) -> Result<Records> {
+    let tables = inputs
+        .iter()
+        .map(|input| read_table(input, options))
+        .collect::<Result<Vec<_>>>()?;
+    concat(tables)
+}
+
+pub fn process_csv_dedupe(input: &str, keys: &[String], options: &ReadOptions) -> Result<Records> {
+    let table = read_table(input, options)?;
+    dedupe(table, keys)
+}
+
+fn input_size(input: &str) -> u64 {
+    if input == "-" {
+        return u64::MAX;
+    }
+    fs::metadata(input).map(|m| m.len()).unwrap_or(u64::MAX)
+}
+
+/// Rows come out in left-file order, then unmatched right rows in right-file order,
+/// whichever side the hash table is built from.
+fn join(left: Table, right: Table, on: &str, kind: JoinKind, build_left: bool) -> Result<Records> {
+    let layout = JoinLayout::new(left.headers, &right.headers, on)?;
+    // (left index, right index) of every output row, `usize::MAX` for a missing side
+    let mut ret: Vec<((usize, usize), Value)> = Vec::new();
+
+    let (build, probe, build_key, probe_key) = if build_left {
+        (
+            left.records,
+            right.records,
+            layout.left_key,
+            layout.right_key,
+        )
+    } else {
+        (
+            right.records,
+            left.records,
+            layout.right_key,
+            layout.left_key,
+        )
+    };
+    let build = build.collect::<Result<Vec<_>>>()?;
+    let mut table: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (idx, record) in build.iter().enumerate() {
+        let key = record.get(build_key).unwrap_or_default();
+        table.entry(key).or_default().push(idx);
+    }
+    let mut matched = vec![false; build.len()];
+    let order = |build_idx: Option<usize>, probe_idx: Option<usize>| {
+        let (build_idx, probe_idx) = (
+            build_idx.unwrap_or(usize::MAX),
+            probe_idx.unwrap_or(usize::MAX),
+        );
+        if build_left {
+            (build_idx, probe_idx)
+        } else {
+            (probe_idx, build_idx)
+        }
+    };
+
+    for (probe_idx, record) in probe.enumerate() {
+        let record = record?;
+        let key = record.get(probe_key).unwrap_or_default();
+        match table.get(key) {
+            Some(indices) => {
+                for &idx in indices {
+                    matched[idx] = true;
+                    let row = if build_left {
+                        layout.merge(Some(&build[idx]), Some(&record))
+                    } else {
+                        layout.merge(Some(&record), Some(&build[idx]))
+                    };
+                    ret.push((order(Some(idx), Some(probe_idx)), row));
+                }
+            }
+            None if build_left && kind == JoinKind::Outer => ret.push((
+                order(None, Some(probe_idx)),
+                layout.merge(None, Some(&record)),
+            )),
+            None if !build_left && kind != JoinKind::Inner => ret.push((
+                order(None, Some(probe_idx)),
+                layout.merge(Some(&record), None),
+            )),
+            None => {}
+        }
+    }
+
+    let keep_unmatched = match kind {
+        JoinKind::Inner => false,
+        JoinKind::Left => build_left,
+        JoinKind::Outer => true,
+    };
+    if keep_unmatched {
+        for (idx, record) in build.iter().enumerate().filter(|&(idx, _)| !matched[idx]) {
+            let row = if build_left {
+                layout.merge(Some(record), None)
+            } else {
+                layout.merge(None, Some(record))
+            };
+            ret.push((order(Some(idx), None), row));
+        }
+    }
+    ret.sort_by_key(|(order, _)| *order);
+    Ok(Records {
+        headers: layout.headers(),
+        rows: ret.into_iter().map(|(_, row)| row).collect(),
+    })
+}
+
+fn concat(tables: Vec<Table>) -> Result<Records> {
+    let mut headers: Vec<String> = Vec::new();
+    for table in &tables {
+        for name in table.headers.iter() {
+            if !headers.iter().any(|h| h == name) {
+                headers.push(name.to_string());
+            }
+        }
+    }
+
+    let mut ret = Vec::new();
+    for Table {
+        headers: local,
+        records,
+    } in tables
+    {
+        for record in records {
+            let record = record?;
+            let mut row = headers
+                .iter()
+                .map(|h| (h.clone(), Value::Null))
+                .collect::<Map<_, _>>();
+            for (name, value) in local.iter().zip(record.iter()) {
+                row.insert(name.to_string(), Value::String(value.to_string()));
+            }
+            ret.push(Value::Object(row));
+        }
+    }
+    Ok(Records { headers, rows: ret })
+}
+
+fn dedupe(table: Table, keys: &[String]) -> Result<Records> {
+    let headers = table.headers;
+    let indices = keys
+        .iter()
+        .map(|key| {
+            headers
+                .iter()
+                .position(|h| h == key)
+                .ok_or_else(|| Error::invalid_input(format!("Key column {:?} not found", key)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut seen = HashSet::new();
+    let mut ret = Vec::new();
+    for record in table.records {
+        let record = record?;
+        let key = if indices.is_empty() {
+            record.iter().map(String::from).collect::<Vec<_>>()
+        } else {
+            indices
+                .iter()
+                .map(|&idx| record.get(idx).unwrap_or_default().to_string())
+                .collect()
+        };
+        if seen.insert(key) {
+            ret.push(headers.iter().zip(record.iter()).collect::<Value>());
+        }
+    }
+    Ok(Records {
+        headers: headers.iter().map(String::from).collect(),
+        rows: ret,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYERS: &str = "id,name\n1,Szczesny\n2,Perin\n3,Buffon\n";
+    const KITS: &str = "id,kit,name\n1,1,Tek\n3,77,Gigi\n4,99,Guest\n";
+
+    fn reader(data: &'static str) -> Table {
+        Table::from_csv(csv::Reader::from_reader(data.as_bytes())).unwrap()
+    }
+
+    fn ids(records: &Records) -> Vec<&str> {
+        records
+            .rows
+            .iter()
+            .map(|r| r["id"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_join_kinds() -> Result<()> {
+        for build_left in [true, false] {
+            let inner = join(
+                reader(PLAYERS),
+                reader(KITS),
+                "id",
+                JoinKind::Inner,
+                build_left,
+            )?;
+            assert_eq!(ids(&inner), vec!["1", "3"]);
+            let buffon = inner.rows.iter().find(|r| r["id"] == "3").unwrap();
+            assert_eq!(buffon["name"], "Buffon");
+            assert_eq!(buffon["kit"], "77");
+            assert_eq!(buffon["name_right"], "Gigi");
+
+            let left = join(
+                reader(PLAYERS),
+                reader(KITS),
+                "id",
+                JoinKind::Left,
+                build_left,
+            )?;
+            assert_eq!(ids(&left), vec!["1", "2", "3"]);
+
+            let outer = join(
+                reader(PLAYERS),
+                reader(KITS),
+                "id",
+                JoinKind::Outer,
+                build_left,
+            )?;
+            assert_eq!(ids(&outer), vec!["1", "2", "3", "4"]);
+            let missing = outer.rows.iter().find(|r| r["id"] == "4").unwrap();
+            assert_eq!(missing["name"], Value::Null);
+            assert_eq!(missing["kit"], "99");
+            assert_eq!(missing["name_right"], "Guest");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_missing_column() {
+        let ret = join(reader(PLAYERS), reader(KITS), "kit", JoinKind::Inner, true);
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_concat_reconciles_headers() -> Result<()> {
+        let ret = concat(vec![reader("a,b\n1,2\n"), reader("b,c\n3,4\n")])?;
+        assert_eq!(ret.headers, vec!["a", "b", "c"]);
+        assert_eq!(ret.rows.len(), 2);
+        assert_eq!(ret.rows[0]["c"], Value::Null);
+        assert_eq!(ret.rows[1]["a"], Value::Null);
+        assert_eq!(ret.rows[1]["b"], "3");
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_order_ignores_build_side() -> Result<()> {
+        let left = "id,name\n3,c\n1,a\n2,b\n";
+        let right = "id,kit\n5,e\n1,x\n3,y\n1,z\n";
+        for kind in [JoinKind::Inner, JoinKind::Left, JoinKind::Outer] {
+            let a = join(reader(left), reader(right), "id", kind, true)?;
+            let b = join(reader(left), reader(right), "id", kind, false)?;
+            assert_eq!(a.rows, b.rows);
+        }
+        let outer = join(reader(left), reader(right), "id", JoinKind::Outer, true)?;
+        assert_eq!(ids(&outer), vec!["3", "1", "1", "2", "5"]);
+        assert_eq!(outer.rows[1]["kit"], "x");
+        assert_eq!(outer.rows[2]["kit"], "z");
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_rename_avoids_collisions() -> Result<()> {
+        let left = "id,name\n1,a\n";
+        let right = "id,name,name_right\n1,b,c\n";
+        let ret = join(reader(left), reader(right), "id", JoinKind::Inner, true)?;
+        assert_eq!(ret.headers, vec!["id", "name", "name_right2", "name_right"]);
+        assert_eq!(ret.rows[0]["name_right2"], "b");
+        assert_eq!(ret.rows[0]["name_right"], "c");
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedupe() -> Result<()> {
+        let data = "a,b\n1,x\n1,y\n2,x\n1,x\n";
+        assert_eq!(dedupe(reader(data), &["a".to_string()])?.rows.len(), 2);
+        assert_eq!(dedupe(reader(data), &[])?.rows.len(), 3);
+        Ok(())
+    }
+}
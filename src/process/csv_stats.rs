@@ -0,0 +1,356 @@
+use crate::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::csv_convert::{read_table, ReadOptions, Table};
+use crate::ColumnType;
+
+/// Number of counters kept per column by the approximate top-k tracker, per requested value.
+const TOP_K_FACTOR: usize = 16;
+
+/// HyperLogLog precision, 2^12 registers give roughly 1.6% standard error.
+const HLL_PRECISION: u32 = 12;
+
+#[derive(Debug, Serialize)]
+pub struct ColumnStats {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+    pub count: usize,
+    pub nulls: usize,
+    pub distinct: u64,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    pub mean: Option<f64>,
+    pub stddev: Option<f64>,
+    pub top: Vec<TopValue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopValue {
+    pub value: String,
+    pub count: usize,
+}
+
+struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+/// Frequency counter, exact or bounded with the space-saving algorithm.
+struct Counter {
+    counts: HashMap<String, usize>,
+    capacity: Option<usize>,
+}
+
+struct ColumnProfile {
+    count: usize,
+    nulls: usize,
+    is_integer: bool,
+    is_float: bool,
+    is_boolean: bool,
+    min_str: Option<String>,
+    max_str: Option<String>,
+    min_num: f64,
+    max_num: f64,
+    /// Exact bounds for integer columns, which f64 cannot hold beyond 2^53
+    min_int: i64,
+    max_int: i64,
+    mean: f64,
+    m2: f64,
+    counter: Counter,
+    hll: Option<HyperLogLog>,
+}
+
+impl HyperLogLog {
+    fn new() -> Self {
+        Self {
+            registers: vec![0; 1 << HLL_PRECISION],
+        }
+    }
+
+    fn insert(&mut self, value: &str) {
+        let hash = blake3::hash(value.as_bytes());
+        let hash = u64::from_le_bytes(hash.as_bytes()[..8].try_into().expect("8 bytes"));
+        let idx = (hash >> (64 - HLL_PRECISION)) as usize;
+        let rank = ((hash << HLL_PRECISION) | (1 << (HLL_PRECISION - 1))).leading_zeros() + 1;
+        self.registers[idx] = self.registers[idx].max(rank as u8);
+    }
+
+    fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+impl Counter {
+    fn new(capacity: Option<usize>) -> Self {
+        Self {
+            counts: HashMap::new(),
+            capacity,
+        }
+    }
+
+    fn insert(&mut self, value: &str) {
+        if let Some(count) = self.counts.get_mut(value) {
+            *count += 1;
+            return;
+        }
+        match self.capacity {
+            Some(capacity) if self.counts.len() >= capacity => {
+                let (min_key, min_count) = self
+                    .counts
+                    .iter()
+                    .min_by_key(|(_, &c)| c)
+                    .map(|(k, &c)| (k.clone(), c))
+                    .expect("counter is full");
+                self.counts.remove(&min_key);
+                self.counts.insert(value.to_string(), min_count + 1);
+            }
+            _ => {
+                self.counts.insert(value.to_string(), 1);
+            }
+        }
+    }
+
+    fn top(&self, k: usize) -> Vec<TopValue> {
+        let mut top = self
+            .counts
+            .iter()
+            .map(|(value, &count)| TopValue {
+                value: value.clone(),
+                count,
+            })
+            .collect::<Vec<_>>();
+        top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        top.truncate(k);
+        top
+    }
+}
+
+impl ColumnProfile {
+    fn new(top: usize, approx: bool) -> Self {
+        let capacity = approx.then(|| (top * TOP_K_FACTOR).max(1));
+        Self {
+            count: 0,
+            nulls: 0,
+            is_integer: true,
+            is_float: true,
+            is_boolean: true,
+            min_str: None,
+            max_str: None,
+            min_num: f64::INFINITY,
+            max_num: f64::NEG_INFINITY,
+            min_int: i64::MAX,
+            max_int: i64::MIN,
+            mean: 0.0,
+            m2: 0.0,
+            counter: Counter::new(capacity),
+            hll: approx.then(HyperLogLog::new),
+        }
+    }
+
+    fn insert(&mut self, value: &str) {
+        if value.is_empty() {
+            self.nulls += 1;
+            return;
+        }
+        self.count += 1;
+        match value.parse::<i64>() {
+            Ok(num) if self.is_integer => {
+                self.min_int = self.min_int.min(num);
+                self.max_int = self.max_int.max(num);
+            }
+            _ => self.is_integer = false,
+        }
+        self.is_boolean &= ColumnType::Boolean.check(value);
+        // NaN and inf parse as f64 but would poison min/max/mean
+        match value.parse::<f64>() {
+            Ok(num) if self.is_float && num.is_finite() => {
+                self.min_num = self.min_num.min(num);
+                self.max_num = self.max_num.max(num);
+                // Welford's online algorithm
+                let delta = num - self.mean;
+                self.mean += delta / self.count as f64;
+                self.m2 += delta * (num - self.mean);
+            }
+            _ => self.is_float = false,
+        }
+        if self.min_str.as_deref().is_none_or(|min| value < min) {
+            self.min_str = Some(value.to_string());
+        }
+        if self.max_str.as_deref().is_none_or(|max| value > max) {
+            self.max_str = Some(value.to_string());
+        }
+        self.counter.insert(value);
+        if let Some(hll) = &mut self.hll {
+            hll.insert(value);
+        }
+    }
+
+    fn finish(self, name: String, top: usize) -> ColumnStats {
+        let column_type = if self.count == 0 {
+            ColumnType::String
+        } else if self.is_integer {
+            ColumnType::Integer
+        } else if self.is_float {
+            ColumnType::Float
+        } else if self.is_boolean {
+            ColumnType::Boolean
+        } else {
+            ColumnType::String
+        };
+        let numeric = matches!(column_type, ColumnType::Integer | ColumnType::Float);
+        let (min, max) = match column_type {
+            ColumnType::Integer => (
+                Some(Value::from(self.min_int)),
+                Some(Value::from(self.max_int)),
+            ),
+            ColumnType::Float => (
+                Some(Value::from(self.min_num)),
+                Some(Value::from(self.max_num)),
+            ),
+            _ => (
+                self.min_str.map(Value::String),
+                self.max_str.map(Value::String),
+            ),
+        };
+        let distinct = match &self.hll {
+            Some(hll) => hll.estimate(),
+            None => self.counter.counts.len() as u64,
+        };
+        ColumnStats {
+            name,
+            column_type,
+            count: self.count,
+            nulls: self.nulls,
+            distinct,
+            min,
+            max,
+            mean: numeric.then_some(self.mean),
+            stddev: (numeric && self.count > 1).then(|| (self.m2 / (self.count - 1) as f64).sqrt()),
+            top: self.counter.top(top),
+        }
+    }
+}
+
+pub fn process_csv_stats(
+    input: &str,
+    options: &ReadOptions,
+    top: usize,
+    approx: bool,
+) -> Result<Vec<ColumnStats>> {
+    let table = read_table(input, options)?;
+    profile(table, top, approx)
+}
+
+fn profile(table: Table, top: usize, approx: bool) -> Result<Vec<ColumnStats>> {
+    let headers = table.headers;
+    let mut profiles = headers
+        .iter()
+        .map(|_| ColumnProfile::new(top, approx))
+        .collect::<Vec<_>>();
+
+    for result in table.records {
+        let record = result?;
+        for (profile, value) in profiles.iter_mut().zip(record.iter()) {
+            profile.insert(value);
+        }
+    }
+
+    let stats = headers
+        .iter()
+        .zip(profiles)
+        .map(|(name, profile)| profile.finish(name.to_string(), top))
+        .collect();
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_csv_stats() -> Result<()> {
+        let stats = process_csv_stats("assets/juventus.csv", &ReadOptions::default(), 3, false)?;
+        assert_eq!(stats.len(), 5);
+
+        let position = &stats[1];
+        assert_eq!(position.name, "Position");
+        assert!(matches!(position.column_type, ColumnType::String));
+        assert_eq!(position.distinct, 10);
+        assert_eq!(position.top[0].value, "Central Midfield");
+        assert_eq!(position.top[0].count, 6);
+
+        let kit = &stats[4];
+        assert!(matches!(kit.column_type, ColumnType::Integer));
+        assert_eq!(kit.count, 27);
+        assert_eq!(kit.min, Some(Value::from(1)));
+        assert!(kit.mean.is_some() && kit.stddev.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_nulls_and_floats() -> Result<()> {
+        let data = "a,b,c\n1.5,,1\n2.5,x,NaN\n,y,inf\n";
+        let stats = profile(
+            Table::from_csv(csv::Reader::from_reader(data.as_bytes()))?,
+            5,
+            false,
+        )?;
+        assert!(matches!(stats[2].column_type, ColumnType::String));
+        assert_eq!(stats[2].mean, None);
+        assert!(matches!(stats[0].column_type, ColumnType::Float));
+        assert_eq!(stats[0].nulls, 1);
+        assert_eq!(stats[0].mean, Some(2.0));
+        assert_eq!(stats[0].max, Some(Value::from(2.5)));
+        assert_eq!(stats[1].nulls, 1);
+        assert_eq!(stats[1].mean, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_integer_bounds_are_exact() -> Result<()> {
+        let data = "n\n9007199254740993\n-9007199254740993\n";
+        let stats = profile(
+            Table::from_csv(csv::Reader::from_reader(data.as_bytes()))?,
+            5,
+            false,
+        )?;
+        assert!(matches!(stats[0].column_type, ColumnType::Integer));
+        assert_eq!(stats[0].max, Some(Value::from(9007199254740993i64)));
+        assert_eq!(stats[0].min, Some(Value::from(-9007199254740993i64)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats_from_xlsx() -> Result<()> {
+        let stats = process_csv_stats("fixture/juventus.xlsx", &ReadOptions::default(), 3, false)?;
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[2].name, "Kit Number");
+        assert!(matches!(stats[2].column_type, ColumnType::Integer));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hyperloglog_estimate() {
+        let mut hll = HyperLogLog::new();
+        for i in 0..50_000 {
+            hll.insert(&i.to_string());
+        }
+        let estimate = hll.estimate() as f64;
+        assert!(
+            (estimate - 50_000.0).abs() / 50_000.0 < 0.05,
+            "{}",
+            estimate
+        );
+    }
+}
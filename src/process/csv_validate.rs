@@ -0,0 +1,265 @@
+use crate::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::csv_convert::{read_table, ReadOptions, Table};
+use crate::utils::get_content;
+
+#[derive(Debug, Deserialize)]
+pub struct CsvSchema {
+    pub columns: Vec<ColumnSchema>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub column_type: ColumnType,
+    #[serde(default = "default_true")]
+    pub required: bool,
+    #[serde(default = "default_true")]
+    pub nullable: bool,
+    #[serde(default)]
+    pub unique: bool,
+    #[serde(default, with = "serde_regex")]
+    pub regex: Option<Regex>,
+    #[serde(default, rename = "enum")]
+    pub values: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    #[default]
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+/// A single schema violation. `row` is the line in the input, `None` for header errors.
+#[derive(Debug, Serialize)]
+pub struct Violation {
+    pub row: Option<u64>,
+    pub column: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub rows: usize,
+    pub violations: Vec<Violation>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+mod serde_regex {
+    use regex::Regex;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern: Option<String> = Option::deserialize(deserializer)?;
+        pattern
+            .map(|p| Regex::new(&p).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+impl CsvSchema {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = get_content(path)?;
+        let schema = serde_yaml::from_slice(&content)?;
+        Ok(schema)
+    }
+}
+
+impl ColumnType {
+    pub(crate) fn check(&self, value: &str) -> bool {
+        match self {
+            ColumnType::String => true,
+            ColumnType::Integer => value.parse::<i64>().is_ok(),
+            ColumnType::Float => value.parse::<f64>().is_ok(),
+            ColumnType::Boolean => matches!(
+                value.to_lowercase().as_str(),
+                "true" | "false" | "yes" | "no" | "1" | "0"
+            ),
+        }
+    }
+}
+
+impl From<ColumnType> for &'static str {
+    fn from(column_type: ColumnType) -> Self {
+        match column_type {
+            ColumnType::String => "string",
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "float",
+            ColumnType::Boolean => "boolean",
+        }
+    }
+}
+
+pub fn process_csv_validate(
+    input: &str,
+    schema: &str,
+    options: &ReadOptions,
+) -> Result<ValidationReport> {
+    let schema = CsvSchema::load(schema)?;
+    let table = read_table(input, options)?;
+    validate(table, &schema)
+}
+
+fn validate(table: Table, schema: &CsvSchema) -> Result<ValidationReport> {
+    let headers = table.headers;
+    let mut violations = Vec::new();
+
+    let mut columns = Vec::with_capacity(schema.columns.len());
+    for column in &schema.columns {
+        match headers.iter().position(|h| h == column.name) {
+            Some(idx) => columns.push((idx, column)),
+            None if column.required => violations.push(Violation {
+                row: None,
+                column: column.name.clone(),
+                message: "required column is missing".to_string(),
+            }),
+            None => {}
+        }
+    }
+
+    let mut seen: HashMap<usize, HashMap<String, u64>> = HashMap::new();
+    let mut rows = 0;
+    for result in table.records {
+        let record = result?;
+        rows += 1;
+        // spreadsheet and parquet rows carry no position; the header is line 1
+        let row = record.position().map_or(rows as u64 + 1, |p| p.line());
+        for &(idx, column) in &columns {
+            let value = record.get(idx).unwrap_or_default();
+            let mut violate = |message: String| {
+                violations.push(Violation {
+                    row: Some(row),
+                    column: column.name.clone(),
+                    message,
+                })
+            };
+
+            if value.is_empty() {
+                if !column.nullable {
+                    violate("value must not be empty".to_string());
+                }
+                continue;
+            }
+            if !column.column_type.check(value) {
+                let expected: &str = column.column_type.into();
+                violate(format!("{:?} is not a valid {}", value, expected));
+            }
+            if let Some(regex) = &column.regex {
+                if !regex.is_match(value) {
+                    violate(format!("{:?} does not match /{}/", value, regex));
+                }
+            }
+            if let Some(values) = &column.values {
+                if !values.iter().any(|v| v == value) {
+                    violate(format!("{:?} is not one of {:?}", value, values));
+                }
+            }
+            if column.unique {
+                let seen = seen.entry(idx).or_default();
+                match seen.get(value) {
+                    Some(first) => violate(format!(
+                        "{:?} is duplicated (first seen at row {})",
+                        value, first
+                    )),
+                    None => {
+                        seen.insert(value.to_string(), row);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(ValidationReport {
+        valid: violations.is_empty(),
+        rows,
+        violations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+columns:
+  - name: Name
+    unique: true
+    nullable: false
+  - name: Position
+    enum: [Goalkeeper, Centre-Back]
+  - name: Kit Number
+    type: integer
+    regex: "^[0-9]{1,2}$"
+  - name: Club
+    required: false
+"#;
+
+    #[test]
+    fn test_process_csv_validate() -> Result<()> {
+        let report = process_csv_validate(
+            "assets/juventus.csv",
+            "fixture/juventus.schema.yaml",
+            &ReadOptions::default(),
+        )?;
+        assert!(report.valid, "{:?}", report.violations);
+        assert_eq!(report.rows, 27);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_violations() -> Result<()> {
+        let schema: CsvSchema = serde_yaml::from_str(SCHEMA)?;
+        let data = "Name,Position,Kit Number\nA,Goalkeeper,1\nA,Striker,x\n,Centre-Back,100\n";
+        let report = validate(
+            Table::from_csv(csv::Reader::from_reader(data.as_bytes()))?,
+            &schema,
+        )?;
+        assert!(!report.valid);
+        let found = report
+            .violations
+            .iter()
+            .map(|v| (v.row.unwrap(), v.column.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (3, "Name"),
+                (3, "Position"),
+                (3, "Kit Number"),
+                (3, "Kit Number"),
+                (4, "Name"),
+                (4, "Kit Number"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_missing_column() -> Result<()> {
+        let schema: CsvSchema = serde_yaml::from_str(SCHEMA)?;
+        let data = "Name,Position\nA,Goalkeeper\n";
+        let report = validate(
+            Table::from_csv(csv::Reader::from_reader(data.as_bytes()))?,
+            &schema,
+        )?;
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(report.violations[0].row, None);
+        assert_eq!(report.violations[0].column, "Kit Number");
+        Ok(())
+    }
+}
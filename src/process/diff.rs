@@ -0,0 +1,208 @@
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use super::convert::read_input;
+use crate::cli::DataFormat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+/// One difference between two documents; `path` is a JSON Pointer.
+#[derive(Debug, Serialize)]
+pub struct DiffEntry {
+    pub op: DiffOp,
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+pub fn process_diff(
+    a: &str,
+    b: &str,
+    from: Option<DataFormat>,
+    key: Option<&str>,
+) -> Result<Vec<DiffEntry>> {
+    let mut a = load(a, from)?;
+    let mut b = load(b, from)?;
+    if let Some(key) = key {
+        a = key_rows(a, key)?;
+        b = key_rows(b, key)?;
+    }
+    let mut entries = Vec::new();
+    diff_values(&a, &b, "", &mut entries);
+    Ok(entries)
+}
+
+/// Render the differences as an RFC 6902 JSON Patch turning `a` into `b`.
+pub fn json_patch(entries: &[DiffEntry]) -> Value {
+    let ops = entries
+        .iter()
+        .map(|entry| match entry.op {
+            DiffOp::Remove => json!({"op": "remove", "path": entry.path}),
+            op => json!({"op": op, "path": entry.path, "value": entry.new}),
+        })
+        .collect();
+    Value::Array(ops)
+}
+
+fn load(input: &str, from: Option<DataFormat>) -> Result<Value> {
+    let docs = read_input(input, from)?;
+    Ok(match docs.len() {
+        1 => docs.into_iter().next().expect("one document"),
+        _ => Value::Array(docs),
+    })
+}
+
+/// Turn a list of rows into an object keyed by `key` so rows are matched by key, not position.
+fn key_rows(value: Value, key: &str) -> Result<Value> {
+    let Value::Array(rows) = value else {
+        return Err(Error::invalid_format("--key requires a list of rows"));
+    };
+    let mut keyed = Map::new();
+    for row in rows {
+        let id = match row.get(key) {
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => {
+                return Err(Error::invalid_format(format!(
+                    "Row without key column {:?}",
+                    key
+                )))
+            }
+        };
+        if keyed.insert(id.clone(), row).is_some() {
+            return Err(Error::invalid_format(format!("Duplicate key {:?}", id)));
+        }
+    }
+    Ok(Value::Object(keyed))
+}
+
+fn diff_values(a: &Value, b: &Value, path: &str, entries: &mut Vec<DiffEntry>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, old) in a {
+                let path = format!("{}/{}", path, escape(key));
+                match b.get(key) {
+                    Some(new) => diff_values(old, new, &path, entries),
+                    None => entries.push(DiffEntry {
+                        op: DiffOp::Remove,
+                        path,
+                        old: Some(old.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for (key, new) in b {
+                if !a.contains_key(key) {
+                    entries.push(DiffEntry {
+                        op: DiffOp::Add,
+                        path: format!("{}/{}", path, escape(key)),
+                        old: None,
+                        new: Some(new.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (idx, (old, new)) in a.iter().zip(b).enumerate() {
+                diff_values(old, new, &format!("{}/{}", path, idx), entries);
+            }
+            for (idx, new) in b.iter().enumerate().skip(a.len()) {
+                entries.push(DiffEntry {
+                    op: DiffOp::Add,
+                    path: format!("{}/{}", path, idx),
+                    old: None,
+                    new: Some(new.clone()),
+                });
+            }
+            // remove from the end so each patch operation stays valid
+            for (idx, old) in a.iter().enumerate().skip(b.len()).rev() {
+                entries.push(DiffEntry {
+                    op: DiffOp::Remove,
+                    path: format!("{}/{}", path, idx),
+                    old: Some(old.clone()),
+                    new: None,
+                });
+            }
+        }
+        (a, b) if a != b => entries.push(DiffEntry {
+            op: DiffOp::Replace,
+            path: path.to_string(),
+            old: Some(a.clone()),
+            new: Some(b.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(a: Value, b: Value) -> Vec<(DiffOp, String)> {
+        let mut entries = Vec::new();
+        diff_values(&a, &b, "", &mut entries);
+        entries.into_iter().map(|e| (e.op, e.path)).collect()
+    }
+
+    #[test]
+    fn test_diff_ignores_key_order() {
+        let a = json!({"a": 1, "b": {"c": [1, 2]}});
+        let b = json!({"b": {"c": [1, 2]}, "a": 1});
+        assert!(diff(a, b).is_empty());
+    }
+
+    #[test]
+    fn test_diff_paths() {
+        let a = json!({"a": 1, "b": {"c": [1, 2, 3]}, "x/y": true});
+        let b = json!({"a": 2, "b": {"c": [1]}, "d": null});
+        assert_eq!(
+            diff(a, b),
+            vec![
+                (DiffOp::Replace, "/a".to_string()),
+                (DiffOp::Remove, "/b/c/2".to_string()),
+                (DiffOp::Remove, "/b/c/1".to_string()),
+                (DiffOp::Remove, "/x~1y".to_string()),
+                (DiffOp::Add, "/d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_patch() {
+        let mut entries = Vec::new();
+        diff_values(
+            &json!({"a": 1, "b": 2}),
+            &json!({"a": 3, "c": 4}),
+            "",
+            &mut entries,
+        );
+        assert_eq!(
+            json_patch(&entries),
+            json!([
+                {"op": "replace", "path": "/a", "value": 3},
+                {"op": "remove", "path": "/b"},
+                {"op": "add", "path": "/c", "value": 4}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_key_rows() -> Result<()> {
+        let a = key_rows(json!([{"id": "1", "v": "a"}, {"id": "2", "v": "b"}]), "id")?;
+        let b = key_rows(json!([{"id": "2", "v": "c"}, {"id": "1", "v": "a"}]), "id")?;
+        assert_eq!(diff(a, b), vec![(DiffOp::Replace, "/2/v".to_string())]);
+        assert!(key_rows(json!([{"id": 1}, {"id": 1}]), "id").is_err());
+        Ok(())
+    }
+}
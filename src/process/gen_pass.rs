@@ -1,67 +1,539 @@
-use rand::prelude::*;
-use zxcvbn::zxcvbn;
+use crate::{Error, Result};
+use rand::{prelude::*, rngs::OsRng};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use zxcvbn::{zxcvbn, Entropy};
 
-const UPPER: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+use crate::utils::get_content;
+
+const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
 
 const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
 
-const NUMBERS: &[u8] = b"123456789";
-
-const SYMBOL: &[u8] = b"!@#$%^&*";
-
-pub fn process_genpass(
-    length: u8,
-    no_uppercase: bool,
-    no_lowercase: bool,
-    no_number: bool,
-    no_symbol: bool,
-) -> anyhow::Result<String> {
-    let mut rng = rand::thread_rng();
-    let mut password = Vec::new();
-    let mut chars = Vec::new();
-    if !no_uppercase {
-        chars.extend_from_slice(UPPER);
-        password.push(
-            *UPPER
-                .choose(&mut rng)
-                .expect("chars won't be empty in this context"),
-        );
-    }
-    if !no_lowercase {
-        chars.extend_from_slice(LOWER);
-        password.push(
-            *LOWER
-                .choose(&mut rng)
-                .expect("chars won't be empty in this context"),
-        );
-    }
-    if !no_number {
-        chars.extend_from_slice(NUMBERS);
-        password.push(
-            *NUMBERS
-                .choose(&mut rng)
-                .expect("chars won't be empty in this context"),
-        );
-    }
-    if !no_symbol {
-        chars.extend_from_slice(SYMBOL);
-        password.push(
-            *SYMBOL
-                .choose(&mut rng)
-                .expect("chars won't be empty in this context"),
-        );
-    }
-    for _ in 0..(length - password.len() as u8) {
-        let c = chars
-            .choose(&mut rng)
-            .expect("chars won't be empty in this context");
-        password.push(*c);
-    }
-    password.shuffle(&mut rng);
-    let password = String::from_utf8(password)?;
-    println!("{:?}", password);
-
-    let estimate = zxcvbn(&password, &[])?;
-    eprintln!("Password strength: {}", estimate.score()); // 3
-    Ok(password)
+const NUMBERS: &[u8] = b"0123456789";
+
+const SYMBOL: &str = "!@#$%^&*";
+
+/// Characters easily confused with one another when read or typed.
+const AMBIGUOUS: &[u8] = b"0O1lI|`'\"";
+
+/// Upper bound on generated password length, well above any real secret.
+pub const MAX_LENGTH: usize = 4096;
+
+/// Upper bound on passphrase word count, well above any real passphrase.
+pub const MAX_WORDS: usize = 512;
+
+/// How many candidates to generate before giving up on `min_score`.
+const MAX_ATTEMPTS: usize = 100;
+
+/// EFF large wordlist: one `<dice roll>\t<word>` entry per line, 7776 words.
+const EFF_LARGE_WORDLIST: &str = include_str!("../../assets/eff_large_wordlist.txt");
+
+/// An organization's password rules, loadable from a YAML/JSON policy file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub uppercase: bool,
+    pub lowercase: bool,
+    pub number: bool,
+    pub symbol: bool,
+    pub symbols: String,
+    pub exclude_ambiguous: bool,
+    pub min_uppercase: usize,
+    pub min_lowercase: usize,
+    pub min_number: usize,
+    pub min_symbol: usize,
+    pub min_score: u8,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            length: 16,
+            uppercase: true,
+            lowercase: true,
+            number: true,
+            symbol: true,
+            symbols: SYMBOL.to_string(),
+            exclude_ambiguous: false,
+            min_uppercase: 1,
+            min_lowercase: 1,
+            min_number: 1,
+            min_symbol: 1,
+            min_score: 0,
+        }
+    }
+}
+
+/// A generated password together with its zxcvbn strength estimate.
+#[derive(Debug, Serialize)]
+pub struct GeneratedPassword {
+    pub password: String,
+    /// Bits of entropy of the generator, not of this particular password.
+    pub entropy: f64,
+    /// Only estimated when asked for or needed to enforce `min_score`.
+    #[serde(flatten)]
+    pub strength: Option<Strength>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Strength {
+    pub score: u8,
+    pub guesses: u64,
+    pub crack_times: CrackTimes,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+/// Human readable crack time estimates for the four zxcvbn attack scenarios.
+#[derive(Debug, Serialize)]
+pub struct CrackTimes {
+    pub online_throttling: String,
+    pub online_no_throttling: String,
+    pub offline_slow_hashing: String,
+    pub offline_fast_hashing: String,
+}
+
+impl From<&Entropy> for Strength {
+    fn from(estimate: &Entropy) -> Self {
+        let times = estimate.crack_times();
+        let feedback = estimate.feedback().as_ref();
+        Self {
+            score: estimate.score(),
+            guesses: estimate.guesses(),
+            crack_times: CrackTimes {
+                online_throttling: times.online_throttling_100_per_hour().to_string(),
+                online_no_throttling: times.online_no_throttling_10_per_second().to_string(),
+                offline_slow_hashing: times.offline_slow_hashing_1e4_per_second().to_string(),
+                offline_fast_hashing: times.offline_fast_hashing_1e10_per_second().to_string(),
+            },
+            warning: feedback.and_then(|f| f.warning()).map(|w| w.to_string()),
+            suggestions: feedback
+                .map(|f| f.suggestions().iter().map(ToString::to_string).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Diceware passphrase settings for `rcli genpass --words`.
+#[derive(Debug, Clone)]
+pub struct PassphrasePolicy {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+    pub number: bool,
+    pub symbol: bool,
+    pub symbols: String,
+    pub min_score: u8,
+}
+
+impl Default for PassphrasePolicy {
+    fn default() -> Self {
+        Self {
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            number: false,
+            symbol: false,
+            symbols: SYMBOL.to_string(),
+            min_score: 0,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = get_content(path)?;
+        let policy = serde_yaml::from_slice(&content)?;
+        Ok(policy)
+    }
+
+    /// Check the policy can be satisfied and return its character classes.
+    fn validate(&self) -> Result<Vec<(Vec<u8>, usize)>> {
+        if self.length == 0 || self.length > MAX_LENGTH {
+            return Err(Error::invalid_input(format!(
+                "Length must be between 1 and {}",
+                MAX_LENGTH
+            )));
+        }
+        if self.min_score > 4 {
+            return Err(Error::invalid_input(
+                "Minimum score must be between 0 and 4",
+            ));
+        }
+        if !self.symbols.is_ascii() {
+            return Err(Error::invalid_input("Symbols must be ASCII characters"));
+        }
+        let classes = self.classes();
+        if classes.is_empty() {
+            return Err(Error::invalid_input(
+                "At least one character class must be enabled",
+            ));
+        }
+        if classes.iter().any(|(chars, _)| chars.is_empty()) {
+            return Err(Error::invalid_input(
+                "A character class is enabled but has no characters left",
+            ));
+        }
+        let required: usize = classes.iter().map(|(_, min)| min).sum();
+        if required > self.length {
+            return Err(Error::invalid_input(format!(
+                "Length {} is shorter than the {} characters required by the policy",
+                self.length, required
+            )));
+        }
+        Ok(classes)
+    }
+
+    /// The enabled character classes with the minimum count required from each.
+    fn classes(&self) -> Vec<(Vec<u8>, usize)> {
+        let classes = [
+            (self.uppercase, UPPER, self.min_uppercase),
+            (self.lowercase, LOWER, self.min_lowercase),
+            (self.number, NUMBERS, self.min_number),
+            (self.symbol, self.symbols.as_bytes(), self.min_symbol),
+        ];
+        classes
+            .into_iter()
+            .filter(|(enabled, _, _)| *enabled)
+            .map(|(_, chars, min)| {
+                let mut chars = chars
+                    .iter()
+                    .copied()
+                    .filter(|c| !(self.exclude_ambiguous && AMBIGUOUS.contains(c)))
+                    .collect::<Vec<_>>();
+                // duplicates in a custom symbol set would bias the draw
+                chars.sort_unstable();
+                chars.dedup();
+                (chars, min)
+            })
+            .collect()
+    }
+}
+
+pub fn process_genpass(policy: &PasswordPolicy, estimate: bool) -> Result<GeneratedPassword> {
+    let classes = policy.validate()?;
+    let alphabet = alphabet(&classes);
+    let entropy = policy.length as f64 * (alphabet.len() as f64).log2();
+    retry(policy.min_score, entropy, estimate, || {
+        Ok(generate(&classes, &alphabet, policy.length, &mut OsRng))
+    })
+}
+
+pub fn process_genphrase(policy: &PassphrasePolicy, estimate: bool) -> Result<GeneratedPassword> {
+    if policy.words == 0 || policy.words > MAX_WORDS {
+        return Err(Error::invalid_input(format!(
+            "Word count must be between 1 and {}",
+            MAX_WORDS
+        )));
+    }
+    if policy.min_score > 4 {
+        return Err(Error::invalid_input(
+            "Minimum score must be between 0 and 4",
+        ));
+    }
+    if policy.symbol && policy.symbols.is_empty() {
+        return Err(Error::invalid_input(
+            "Symbol insertion requires a non-empty symbol set",
+        ));
+    }
+    let wordlist = wordlist();
+    let mut symbols = policy.symbols.chars().collect::<Vec<_>>();
+    symbols.sort_unstable();
+    symbols.dedup();
+
+    // each insertion picks a character and the word it is appended to
+    let positions = (policy.words as f64).log2();
+    let mut entropy = policy.words as f64 * (wordlist.len() as f64).log2();
+    if policy.number {
+        entropy += 10f64.log2() + positions;
+    }
+    if policy.symbol {
+        entropy += (symbols.len() as f64).log2() + positions;
+    }
+
+    let mut rng = OsRng;
+    retry(policy.min_score, entropy, estimate, || {
+        let mut words = (0..policy.words)
+            .map(|_| {
+                let word = *wordlist.choose(&mut rng).expect("wordlist is not empty");
+                if policy.capitalize {
+                    capitalize(word)
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect::<Vec<_>>();
+        if policy.number {
+            let digit = rng.gen_range(0..10);
+            words[rng.gen_range(0..policy.words)].push_str(&digit.to_string());
+        }
+        if policy.symbol {
+            let symbol = *symbols.choose(&mut rng).expect("symbols is not empty");
+            words[rng.gen_range(0..policy.words)].push(symbol);
+        }
+        Ok(words.join(&policy.separator))
+    })
+}
+
+/// Generate candidates until one reaches `min_score`, scoring them with zxcvbn only
+/// when there is a minimum to check or `estimate` asks for the strength.
+fn retry(
+    min_score: u8,
+    entropy: f64,
+    estimate: bool,
+    mut generate: impl FnMut() -> Result<String>,
+) -> Result<GeneratedPassword> {
+    for _ in 0..MAX_ATTEMPTS {
+        let password = generate()?;
+        if min_score == 0 && !estimate {
+            return Ok(GeneratedPassword {
+                strength: None,
+                password,
+                entropy,
+            });
+        }
+        let estimate = zxcvbn(&password, &[]).map_err(|e| Error::invalid_input(e.to_string()))?;
+        if estimate.score() >= min_score {
+            return Ok(GeneratedPassword {
+                strength: Some(Strength::from(&estimate)),
+                password,
+                entropy,
+            });
+        }
+    }
+    Err(Error::invalid_input(format!(
+        "Could not reach strength score {} in {} attempts",
+        min_score, MAX_ATTEMPTS
+    )))
+}
+
+/// The EFF words without the four hyphenated ones (`drop-down`, `felt-tip`, `t-shirt`,
+/// `yo-yo`), so a phrase joined with the default `-` splits back into its words.
+fn wordlist() -> &'static [&'static str] {
+    static WORDLIST: OnceLock<Vec<&'static str>> = OnceLock::new();
+    WORDLIST.get_or_init(|| {
+        EFF_LARGE_WORDLIST
+            .lines()
+            .filter_map(|line| line.split('\t').nth(1))
+            .filter(|word| !word.contains('-'))
+            .collect()
+    })
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Every character any class may produce, each listed once so the fill is uniform.
+fn alphabet(classes: &[(Vec<u8>, usize)]) -> Vec<u8> {
+    let mut chars = classes
+        .iter()
+        .flat_map(|(chars, _)| chars.iter().copied())
+        .collect::<Vec<_>>();
+    chars.sort_unstable();
+    chars.dedup();
+    chars
+}
+
+/// Draw the per-class minimums first, fill the rest from the whole alphabet, then shuffle.
+fn generate(
+    classes: &[(Vec<u8>, usize)],
+    alphabet: &[u8],
+    length: usize,
+    rng: &mut (impl Rng + CryptoRng),
+) -> String {
+    let mut password = Vec::with_capacity(length);
+    for (class, min) in classes {
+        for _ in 0..*min {
+            password.push(*class.choose(rng).expect("class is not empty"));
+        }
+    }
+    while password.len() < length {
+        password.push(*alphabet.choose(rng).expect("alphabet is not empty"));
+    }
+    password.shuffle(rng);
+    password.into_iter().map(char::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Generate without the zxcvbn estimate, which dominates test time in debug builds.
+    fn generate_unscored(policy: &PasswordPolicy) -> Result<String> {
+        let classes = policy.validate()?;
+        Ok(generate(
+            &classes,
+            &alphabet(&classes),
+            policy.length,
+            &mut OsRng,
+        ))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_length_and_class_coverage(
+            length in 4usize..600,
+            mins in (0usize..4, 0usize..4, 0usize..4, 0usize..4),
+            exclude_ambiguous in any::<bool>(),
+        ) {
+            let policy = PasswordPolicy {
+                length,
+                exclude_ambiguous,
+                min_uppercase: mins.0,
+                min_lowercase: mins.1,
+                min_number: mins.2,
+                min_symbol: mins.3,
+                ..Default::default()
+            };
+            match generate_unscored(&policy) {
+                Ok(password) => {
+                    prop_assert_eq!(password.chars().count(), length);
+                    let count = |f: fn(&char) -> bool| password.chars().filter(f).count();
+                    prop_assert!(count(char::is_ascii_uppercase) >= mins.0);
+                    prop_assert!(count(char::is_ascii_lowercase) >= mins.1);
+                    prop_assert!(count(char::is_ascii_digit) >= mins.2);
+                    prop_assert!(count(|c| SYMBOL.contains(*c)) >= mins.3);
+                }
+                Err(_) => prop_assert!(mins.0 + mins.1 + mins.2 + mins.3 > length),
+            }
+        }
+
+        #[test]
+        fn prop_only_enabled_classes(
+            classes in (any::<bool>(), any::<bool>(), any::<bool>(), any::<bool>()),
+            length in 1usize..64,
+        ) {
+            let policy = PasswordPolicy {
+                length,
+                uppercase: classes.0,
+                lowercase: classes.1,
+                number: classes.2,
+                symbol: classes.3,
+                min_uppercase: 0,
+                min_lowercase: 0,
+                min_number: 0,
+                min_symbol: 0,
+                ..Default::default()
+            };
+            let result = generate_unscored(&policy);
+            if !(classes.0 || classes.1 || classes.2 || classes.3) {
+                prop_assert!(result.is_err());
+                return Ok(());
+            }
+            let password = result.expect("valid policy");
+            prop_assert_eq!(password.len(), length);
+            for c in password.chars() {
+                prop_assert!(
+                    (classes.0 && c.is_ascii_uppercase())
+                        || (classes.1 && c.is_ascii_lowercase())
+                        || (classes.2 && c.is_ascii_digit())
+                        || (classes.3 && SYMBOL.contains(c))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_policy_minimums() -> Result<()> {
+        let policy = PasswordPolicy {
+            length: 12,
+            lowercase: false,
+            symbols: "-_".to_string(),
+            min_number: 4,
+            min_symbol: 3,
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let password = process_genpass(&policy, false)?.password;
+            assert_eq!(password.len(), 12);
+            assert!(password.chars().filter(char::is_ascii_digit).count() >= 4);
+            assert!(password.chars().filter(|c| "-_".contains(*c)).count() >= 3);
+            assert!(!password.chars().any(|c| c.is_ascii_lowercase()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_exclude_ambiguous() -> Result<()> {
+        let policy = PasswordPolicy {
+            length: 64,
+            exclude_ambiguous: true,
+            ..Default::default()
+        };
+        let password = process_genpass(&policy, false)?.password;
+        assert!(!password.bytes().any(|c| AMBIGUOUS.contains(&c)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_passphrase() -> Result<()> {
+        assert_eq!(wordlist().len(), 7772);
+        let phrase = process_genphrase(&PassphrasePolicy::default(), false)?.password;
+        assert_eq!(phrase.split('-').count(), 6);
+        let policy = PassphrasePolicy {
+            words: 5,
+            separator: " ".to_string(),
+            capitalize: true,
+            number: true,
+            ..Default::default()
+        };
+        let phrase = process_genphrase(&policy, false)?.password;
+        let words = phrase.split(' ').collect::<Vec<_>>();
+        assert_eq!(words.len(), 5);
+        assert!(words
+            .iter()
+            .all(|w| w.starts_with(|c: char| c.is_uppercase())));
+        assert_eq!(phrase.chars().filter(char::is_ascii_digit).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_strength_estimate() -> Result<()> {
+        let policy = PasswordPolicy::default();
+        assert!(process_genpass(&policy, false)?.strength.is_none());
+        assert!(process_genpass(&policy, true)?.strength.is_some());
+        let policy = PasswordPolicy {
+            min_score: 3,
+            ..Default::default()
+        };
+        let strength = process_genpass(&policy, false)?.strength;
+        assert!(strength.is_some_and(|s| s.score >= 3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_errors() -> Result<()> {
+        let too_short = PasswordPolicy {
+            length: 3,
+            ..Default::default()
+        };
+        assert!(process_genpass(&too_short, false).is_err());
+        for length in [0, MAX_LENGTH + 1] {
+            let policy = PasswordPolicy {
+                length,
+                ..Default::default()
+            };
+            assert!(process_genpass(&policy, false).is_err());
+        }
+        let long = PasswordPolicy {
+            length: 512,
+            ..Default::default()
+        };
+        assert_eq!(process_genpass(&long, false)?.password.len(), 512);
+
+        let policy: PasswordPolicy =
+            serde_yaml::from_str("length: 24\nsymbols: \"#-\"\nmin_score: 4\n")?;
+        assert_eq!(policy.length, 24);
+        assert!(policy.uppercase);
+        assert!(serde_yaml::from_str::<PasswordPolicy>("lenght: 24\n").is_err());
+        Ok(())
+    }
 }
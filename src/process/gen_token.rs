@@ -0,0 +1,116 @@
+use std::{
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Error, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use data_encoding::{BASE32_NOPAD, HEXLOWER};
+use rand::{rngs::OsRng, seq::SliceRandom, Rng, RngCore};
+use ulid::Ulid;
+use uuid::{Builder, Uuid};
+
+use crate::cli::{TokenEncoding, UuidVersion};
+
+/// URL-friendly alphabet used by nanoid by default.
+pub const NANOID_ALPHABET: &str =
+    "_-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+const MAX_TOKEN_BYTES: usize = 1024;
+
+pub fn process_gen_token(bytes: usize, encoding: TokenEncoding) -> Result<String> {
+    if bytes == 0 || bytes > MAX_TOKEN_BYTES {
+        return Err(Error::invalid_input(format!(
+            "Token size must be between 1 and {} bytes",
+            MAX_TOKEN_BYTES
+        )));
+    }
+    let mut buf = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buf);
+    Ok(match encoding {
+        TokenEncoding::Base64Url => URL_SAFE_NO_PAD.encode(&buf),
+        TokenEncoding::Hex => HEXLOWER.encode(&buf),
+        TokenEncoding::Base32 => BASE32_NOPAD.encode(&buf),
+    })
+}
+
+pub fn process_gen_uuid(version: UuidVersion) -> Result<Uuid> {
+    Ok(match version {
+        UuidVersion::V4 => Builder::from_random_bytes(OsRng.gen()).into_uuid(),
+        UuidVersion::V7 => {
+            let random: [u8; 10] = OsRng.gen();
+            Builder::from_unix_timestamp_millis(unix_millis()?, &random).into_uuid()
+        }
+    })
+}
+
+pub fn process_gen_ulid() -> Result<Ulid> {
+    Ok(Ulid::from_parts(unix_millis()?, OsRng.gen()))
+}
+
+pub fn process_gen_nanoid(alphabet: &str, size: usize) -> Result<String> {
+    let mut chars = alphabet.chars().collect::<Vec<_>>();
+    chars.sort_unstable();
+    chars.dedup();
+    if chars.len() != alphabet.chars().count() {
+        return Err(Error::invalid_input(
+            "Alphabet must not contain duplicate characters",
+        ));
+    }
+    if chars.len() < 2 {
+        return Err(Error::invalid_input(
+            "Alphabet needs at least two characters",
+        ));
+    }
+    if size == 0 || size > MAX_TOKEN_BYTES {
+        return Err(Error::invalid_input(format!(
+            "Size must be between 1 and {}",
+            MAX_TOKEN_BYTES
+        )));
+    }
+    Ok((0..size)
+        .map(|_| *chars.choose(&mut OsRng).expect("alphabet is not empty"))
+        .collect())
+}
+
+fn unix_millis() -> Result<u64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(io::Error::other)?
+        .as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gen_token() -> Result<()> {
+        assert_eq!(process_gen_token(32, TokenEncoding::Base64Url)?.len(), 43);
+        assert_eq!(process_gen_token(32, TokenEncoding::Hex)?.len(), 64);
+        assert_eq!(process_gen_token(5, TokenEncoding::Base32)?.len(), 8);
+        assert!(process_gen_token(0, TokenEncoding::Hex).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_ids() -> Result<()> {
+        assert_eq!(process_gen_uuid(UuidVersion::V4)?.get_version_num(), 4);
+        let first = process_gen_uuid(UuidVersion::V7)?;
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = process_gen_uuid(UuidVersion::V7)?;
+        assert_eq!(first.get_version_num(), 7);
+        assert!(first < second);
+        assert_eq!(process_gen_ulid()?.to_string().len(), 26);
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_nanoid() -> Result<()> {
+        let id = process_gen_nanoid("ab", 30)?;
+        assert_eq!(id.len(), 30);
+        assert!(id.chars().all(|c| c == 'a' || c == 'b'));
+        assert!(process_gen_nanoid("aab", 10).is_err());
+        Ok(())
+    }
+}
@@ -1,131 +1,970 @@
-use anyhow::Result;
+use crate::{CryptoError, DotfilePolicy, Error, Result};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::get,
-    Router,
+    Json, Router,
 };
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
-use tower_http::services::ServeDir;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    io::{self, SeekFrom},
+    net::SocketAddr,
+    path::{Component, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
 use tracing::{info, warn};
 
+/// Path segments keep RFC 3986 unreserved characters, everything else is escaped.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// How long open connections may keep running after a shutdown signal.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// Access rules for the served directory.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ServePolicy {
+    /// Serve symlinks that resolve to a path outside the root
+    pub follow_symlinks: bool,
+    pub dotfiles: DotfilePolicy,
+}
+
 struct AppState {
+    /// Canonical root directory
     path: PathBuf,
+    policy: ServePolicy,
 }
 
-pub async fn process_http_serve(path: PathBuf, port: u16) -> Result<()> {
-    let socket = SocketAddr::from(([127, 0, 0, 1], port));
-    info!("Serving {:?} on {}", path, socket);
-    let app_state = Arc::new(AppState { path: path.clone() });
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortKey {
+    #[default]
+    Name,
+    Size,
+    Modified,
+}
 
-    let service = ServeDir::new(path);
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
 
-    let app = Router::new()
-        .route("/", get(dir_handler))
-        .route("/*path", get(file_handler))
-        .nest_service("/file", service)
-        .with_state(app_state);
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ListingQuery {
+    sort: SortKey,
+    order: SortOrder,
+}
 
-    let listener = tokio::net::TcpListener::bind(socket).await?;
-    axum::serve(listener, app).await.unwrap();
-    Ok(())
+#[derive(Debug, Serialize)]
+struct Listing {
+    path: String,
+    parent: Option<String>,
+    entries: Vec<ListingEntry>,
 }
 
-async fn dir_handler(State(app_state): State<Arc<AppState>>) -> (StatusCode, String) {
-    let mut content = String::new();
-    content.push_str(
-        r#"<!DOCTYPE html>
-        <html lang="en">
-        <head>
-          <title>Index of the document</title>
-        </head>
-        <body>
+#[derive(Debug, Serialize)]
+struct ListingEntry {
+    name: String,
+    href: String,
+    is_dir: bool,
+    size: u64,
+    /// Unix time in seconds
+    modified: Option<u64>,
+}
 
-        <ul>
+/// Where the HTTPS certificate and private key come from.
+#[derive(Debug, Clone)]
+pub enum TlsSource {
+    /// PEM encoded certificate chain and private key
+    Pem { cert: PathBuf, key: PathBuf },
+    /// Throwaway certificate generated at startup, for local testing only
+    SelfSigned,
+}
 
-        "#,
-    );
-    let entries = tokio::fs::read_dir(std::path::Path::new(&app_state.path)).await;
-    match entries {
-        Err(e) => {
-            warn!("Error reading directory: {:?}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Error reading directory".to_string(),
-            );
+pub async fn process_http_serve(
+    path: PathBuf,
+    addr: SocketAddr,
+    tls: Option<TlsSource>,
+    policy: ServePolicy,
+) -> Result<()> {
+    let app_state = Arc::new(AppState {
+        path: tokio::fs::canonicalize(&path).await?,
+        policy,
+    });
+    let app = router(app_state).into_make_service();
+
+    let handle = Handle::new();
+    tokio::spawn(shutdown_on_signal(handle.clone()));
+
+    match tls {
+        Some(tls) => {
+            let config = rustls_config(&tls, addr).await?;
+            info!("Serving {:?} on https://{}", path, addr);
+            axum_server::bind_rustls(addr, config)
+                .handle(handle)
+                .serve(app)
+                .await?;
+        }
+        None => {
+            info!("Serving {:?} on http://{}", path, addr);
+            axum_server::bind(addr).handle(handle).serve(app).await?;
         }
-        Ok(mut entries) => {
-            while let Ok(entry) = entries.next_entry().await {
-                if entry.is_none() {
-                    break;
-                }
-                match entry {
-                    Some(entry) => {
-                        let path = entry.path();
-                        let name = path.file_name().unwrap().to_string_lossy().to_string();
-                        content.push_str(&format!("<li><a href=\"{}/\">{}/</a></li>", name, name));
-                    }
-                    None => {
-                        continue;
-                    }
-                }
+    }
+    info!("Server stopped");
+    Ok(())
+}
+
+async fn rustls_config(tls: &TlsSource, addr: SocketAddr) -> Result<RustlsConfig> {
+    // axum-server is built without a default provider; a second install is a no-op
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let config = match tls {
+        TlsSource::Pem { cert, key } => RustlsConfig::from_pem_file(cert, key).await,
+        TlsSource::SelfSigned => {
+            let mut names = vec![
+                "localhost".to_string(),
+                "127.0.0.1".to_string(),
+                "::1".to_string(),
+            ];
+            if !addr.ip().is_unspecified() && !addr.ip().is_loopback() {
+                names.push(addr.ip().to_string());
+            }
+            let certified = rcgen::generate_simple_self_signed(names)
+                .map_err(|e| CryptoError::Tls(e.to_string()))?;
+            warn!("Using a self-signed certificate, clients will not trust it");
+            RustlsConfig::from_pem(
+                certified.cert.pem().into_bytes(),
+                certified.key_pair.serialize_pem().into_bytes(),
+            )
+            .await
+        }
+    };
+    config.map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => Error::Io(e),
+        _ => CryptoError::Tls(e.to_string()).into(),
+    })
+}
+
+/// Stops accepting connections on SIGINT or SIGTERM and gives in-flight
+/// requests a few seconds to finish.
+async fn shutdown_on_signal(handle: Handle) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
             }
         }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
     }
+    info!("Shutting down, waiting for open connections");
+    handle.graceful_shutdown(Some(SHUTDOWN_GRACE));
+}
 
-    content.push_str(
-        r##"
-        </ul>
-        </body>
-        </html>
-        "##,
-    );
-    (StatusCode::OK, content)
+fn router(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(dir_handler))
+        .route("/*path", get(file_handler))
+        .with_state(app_state)
+}
+
+async fn dir_handler(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<ListingQuery>,
+    headers: HeaderMap,
+) -> Response {
+    list_dir(&app_state, &app_state.path, "", &query, &headers).await
 }
 
 async fn file_handler(
     State(app_state): State<Arc<AppState>>,
     Path(path): Path<String>,
-) -> (StatusCode, String) {
-    let file_path = std::path::Path::new(&app_state.path).join(path);
-    info!("File requested: {:?}", file_path);
+    Query(query): Query<ListingQuery>,
+    headers: HeaderMap,
+) -> Response {
+    info!("File requested: {:?}", path);
+    let file_path = match resolve(&app_state, &path).await {
+        Ok(file_path) => file_path,
+        Err((status, message)) => {
+            warn!("Rejected {:?}: {}", path, message);
+            return (status, message).into_response();
+        }
+    };
 
-    if file_path.is_dir() {
-        let app_state = Arc::new(AppState { path: file_path });
-        return dir_handler(State(app_state)).await;
+    let is_dir = tokio::fs::metadata(&file_path)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir());
+    if is_dir {
+        return list_dir(&app_state, &file_path, &path, &query, &headers).await;
     }
 
-    if !file_path.exists() {
-        warn!("File not found: {:?}", file_path);
-        return (StatusCode::NOT_FOUND, "File not found".to_string());
-    }
-    match tokio::fs::read_to_string(&file_path).await {
+    match serve_file(&file_path, &headers).await {
         Err(e) => {
             warn!("Error reading file: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Error reading file".to_string(),
-            )
+            (StatusCode::INTERNAL_SERVER_ERROR, "Error reading file").into_response()
         }
-        Ok(content) => {
+        Ok(response) => {
             info!("File served: {:?}", &file_path);
-            (StatusCode::OK, content)
+            response
+        }
+    }
+}
+
+/// Map a decoded url path onto a canonical path under the root. `..`, absolute
+/// paths and, unless allowed, symlinks leading out of the root are refused.
+async fn resolve(
+    app_state: &AppState,
+    path: &str,
+) -> std::result::Result<PathBuf, (StatusCode, &'static str)> {
+    let mut relative = PathBuf::new();
+    for component in std::path::Path::new(path).components() {
+        match component {
+            Component::Normal(name) => relative.push(name),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err((StatusCode::BAD_REQUEST, "Invalid path"));
+            }
         }
     }
+    check_dotfiles(&relative, app_state.policy.dotfiles)?;
+
+    let resolved = tokio::fs::canonicalize(app_state.path.join(&relative))
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "File not found"))?;
+    match resolved.strip_prefix(&app_state.path) {
+        // a symlink may point at a dotfile under another name
+        Ok(inside) => check_dotfiles(inside, app_state.policy.dotfiles)?,
+        Err(_) if app_state.policy.follow_symlinks => {}
+        Err(_) => return Err((StatusCode::FORBIDDEN, "Forbidden")),
+    }
+    Ok(resolved)
+}
+
+fn check_dotfiles(
+    path: &std::path::Path,
+    policy: DotfilePolicy,
+) -> std::result::Result<(), (StatusCode, &'static str)> {
+    if policy == DotfilePolicy::Allow || !path.components().any(is_dotfile) {
+        return Ok(());
+    }
+    match policy {
+        DotfilePolicy::Deny => Err((StatusCode::FORBIDDEN, "Forbidden")),
+        _ => Err((StatusCode::NOT_FOUND, "File not found")),
+    }
+}
+
+fn is_dotfile(component: Component) -> bool {
+    matches!(component, Component::Normal(name) if name.as_encoded_bytes().starts_with(b"."))
+}
+
+/// Stream `path` with caching validators, answering conditional and range requests.
+async fn serve_file(path: &std::path::Path, headers: &HeaderMap) -> std::io::Result<Response> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let metadata = file.metadata().await?;
+    let len = metadata.len();
+    let modified = metadata.modified().ok();
+    let etag = entity_tag(len, modified);
+
+    let mut builder = Response::builder()
+        .header(header::ETAG, &etag)
+        .header(header::ACCEPT_RANGES, "bytes");
+    if let Some(modified) = modified {
+        builder = builder.header(header::LAST_MODIFIED, httpdate::fmt_http_date(modified));
+    }
+    if is_not_modified(headers, &etag, modified) {
+        return Ok(builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .expect("valid response"));
+    }
+
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    let builder = builder.header(header::CONTENT_TYPE, mime.as_ref());
+    let (status, start, count, builder) = match byte_range(headers, len, &etag, modified) {
+        ByteRange::Full => (StatusCode::OK, 0, len, builder),
+        ByteRange::Partial(start, end) => (
+            StatusCode::PARTIAL_CONTENT,
+            start,
+            end - start + 1,
+            builder.header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, len),
+            ),
+        ),
+        ByteRange::Unsatisfiable => {
+            return Ok(builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Body::empty())
+                .expect("valid response"));
+        }
+    };
+    file.seek(SeekFrom::Start(start)).await?;
+    let body = Body::from_stream(ReaderStream::new(file.take(count)));
+    Ok(builder
+        .status(status)
+        .header(header::CONTENT_LENGTH, count)
+        .body(body)
+        .expect("valid response"))
+}
+
+/// Size and modification time identify a version well enough without hashing.
+fn entity_tag(len: u64, modified: Option<SystemTime>) -> String {
+    let nanos = modified
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos());
+    format!("\"{:x}-{:x}\"", len, nanos)
+}
+
+/// RFC 9110 13.2.2: If-None-Match takes precedence over If-Modified-Since.
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = header_str(headers, header::IF_NONE_MATCH) {
+        return if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+    }
+    match (header_str(headers, header::IF_MODIFIED_SINCE), modified) {
+        (Some(since), Some(modified)) => httpdate::parse_http_date(since)
+            .is_ok_and(|since| unix_secs(modified) <= unix_secs(since)),
+        _ => false,
+    }
+}
+
+enum ByteRange {
+    Full,
+    /// Inclusive start and end offsets
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Only single `bytes=` ranges are honoured; anything else gets the full body,
+/// which RFC 9110 allows.
+fn byte_range(
+    headers: &HeaderMap,
+    len: u64,
+    etag: &str,
+    modified: Option<SystemTime>,
+) -> ByteRange {
+    let Some(range) = header_str(headers, header::RANGE) else {
+        return ByteRange::Full;
+    };
+    if let Some(if_range) = header_str(headers, header::IF_RANGE) {
+        let fresh = match httpdate::parse_http_date(if_range) {
+            Ok(date) => modified.is_some_and(|m| unix_secs(m) == unix_secs(date)),
+            Err(_) => if_range == etag,
+        };
+        if !fresh {
+            return ByteRange::Full;
+        }
+    }
+    let Some((start, end)) = range
+        .strip_prefix("bytes=")
+        .filter(|spec| !spec.contains(','))
+        .and_then(|spec| spec.trim().split_once('-'))
+    else {
+        return ByteRange::Full;
+    };
+    let (start, end) = match (start.parse::<u64>(), end.parse::<u64>()) {
+        // bytes=-N is the last N bytes
+        (Err(_), Ok(suffix)) if start.is_empty() => {
+            if suffix == 0 || len == 0 {
+                return ByteRange::Unsatisfiable;
+            }
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (Ok(start), Err(_)) if end.is_empty() => (start, len.saturating_sub(1)),
+        (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+        _ => return ByteRange::Full,
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(start, end)
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// List `dir`, served at the url path `rel`, as HTML or as JSON when the
+/// client accepts it.
+async fn list_dir(
+    app_state: &AppState,
+    dir: &std::path::Path,
+    rel: &str,
+    query: &ListingQuery,
+    headers: &HeaderMap,
+) -> Response {
+    let listing = match read_listing(app_state, dir, rel, query).await {
+        Ok(listing) => listing,
+        Err(e) => {
+            warn!("Error reading directory: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Error reading directory").into_response();
+        }
+    };
+    let wants_json = header_str(headers, header::ACCEPT)
+        .is_some_and(|accept| accept.contains("application/json"));
+    if wants_json {
+        Json(listing).into_response()
+    } else {
+        let html = render_listing(&listing, query);
+        ([(header::CONTENT_TYPE, "text/html; charset=utf-8")], html).into_response()
+    }
+}
+
+async fn read_listing(
+    app_state: &AppState,
+    dir: &std::path::Path,
+    rel: &str,
+    query: &ListingQuery,
+) -> std::io::Result<Listing> {
+    let segments = rel
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| utf8_percent_encode(s, PATH_SEGMENT).to_string())
+        .collect::<Vec<_>>();
+    let base = match segments.is_empty() {
+        true => "/".to_string(),
+        false => format!("/{}/", segments.join("/")),
+    };
+    let parent = match segments.split_last() {
+        None => None,
+        Some((_, [])) => Some("/".to_string()),
+        Some((_, rest)) => Some(format!("/{}/", rest.join("/"))),
+    };
+
+    let mut entries = Vec::new();
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // only list what resolve() would serve
+        let Ok(resolved) = tokio::fs::canonicalize(entry.path()).await else {
+            continue;
+        };
+        let inside = resolved.strip_prefix(&app_state.path).ok();
+        let hidden = match inside {
+            Some(inside) => check_dotfiles(inside, app_state.policy.dotfiles).is_err(),
+            None => !app_state.policy.follow_symlinks,
+        };
+        if hidden || (name.starts_with('.') && app_state.policy.dotfiles != DotfilePolicy::Allow) {
+            continue;
+        }
+        // an entry that vanished or cannot be read is left out, not a failed listing
+        let Ok(metadata) = tokio::fs::metadata(&resolved).await else {
+            continue;
+        };
+        let is_dir = metadata.is_dir();
+        let mut href = format!("{}{}", base, utf8_percent_encode(&name, PATH_SEGMENT));
+        if is_dir {
+            href.push('/');
+        }
+        entries.push(ListingEntry {
+            name,
+            href,
+            is_dir,
+            size: if is_dir { 0 } else { metadata.len() },
+            modified: metadata.modified().ok().and_then(unix_secs),
+        });
+    }
+    sort_entries(&mut entries, query);
+
+    Ok(Listing {
+        path: base,
+        parent,
+        entries,
+    })
+}
+
+/// Directories always come first; ties fall back to the name.
+fn sort_entries(entries: &mut [ListingEntry], query: &ListingQuery) {
+    entries.sort_by(|a, b| {
+        let ordering = match query.sort {
+            SortKey::Name => Ordering::Equal,
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+        }
+        .then_with(|| a.name.cmp(&b.name));
+        let ordering = match query.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        };
+        b.is_dir.cmp(&a.is_dir).then(ordering)
+    });
+}
+
+fn render_listing(listing: &Listing, query: &ListingQuery) -> String {
+    let path = percent_decode_str(&listing.path).decode_utf8_lossy();
+    let title = html_escape::encode_text(&path);
+    let mut content = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Index of {title}</title>
+</head>
+<body>
+<h1>Index of {title}</h1>
+<table>
+<thead>
+<tr><th>{}</th><th>{}</th><th>{}</th></tr>
+</thead>
+<tbody>
+"#,
+        sort_link("Name", SortKey::Name, query),
+        sort_link("Size", SortKey::Size, query),
+        sort_link("Modified", SortKey::Modified, query),
+    );
+    if let Some(parent) = &listing.parent {
+        content.push_str(&format!(
+            "<tr><td><a href=\"{}\">../</a></td><td></td><td></td></tr>\n",
+            html_escape::encode_double_quoted_attribute(parent)
+        ));
+    }
+    for entry in &listing.entries {
+        let suffix = if entry.is_dir { "/" } else { "" };
+        let size = if entry.is_dir {
+            "-".to_string()
+        } else {
+            human_size(entry.size)
+        };
+        content.push_str(&format!(
+            "<tr><td><a href=\"{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>\n",
+            html_escape::encode_double_quoted_attribute(&entry.href),
+            html_escape::encode_text(&entry.name),
+            suffix,
+            size,
+            entry.modified.map(format_time).unwrap_or_default(),
+        ));
+    }
+    content.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    content
+}
+
+/// Column header link; clicking the active column flips the order.
+fn sort_link(label: &str, key: SortKey, query: &ListingQuery) -> String {
+    let order = match (query.sort == key, query.order) {
+        (true, SortOrder::Asc) => "desc",
+        _ => "asc",
+    };
+    let key = match key {
+        SortKey::Name => "name",
+        SortKey::Size => "size",
+        SortKey::Modified => "modified",
+    };
+    format!(
+        "<a href=\"?sort={}&amp;order={}\">{}</a>",
+        key, order, label
+    )
+}
+
+fn human_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+fn unix_secs(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+fn format_time(secs: u64) -> String {
+    let format = time::macros::format_description!("[year]-[month]-[day] [hour]:[minute]");
+    time::OffsetDateTime::from_unix_timestamp(secs as i64)
+        .ok()
+        .and_then(|time| time.format(format).ok())
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn app_state(root: impl AsRef<std::path::Path>, policy: ServePolicy) -> Arc<AppState> {
+        Arc::new(AppState {
+            path: std::fs::canonicalize(root).expect("root exists"),
+            policy,
+        })
+    }
+
+    async fn body_string(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        String::from_utf8(body.to_vec()).expect("utf-8 body")
+    }
+
+    fn listing_request(path: &str, json: bool) -> (Path<String>, HeaderMap) {
+        let mut headers = HeaderMap::new();
+        if json {
+            headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+        }
+        (Path(path.to_string()), headers)
+    }
 
     #[tokio::test]
     async fn test_file_handler() {
         let path = "Cargo.toml".to_string();
-        let app_state = Arc::new(AppState {
-            path: PathBuf::from("."),
-        });
-        let (state_code, content) = file_handler(State(app_state), Path(path)).await;
-        assert_eq!(state_code, StatusCode::OK);
-        assert!(content.trim().contains("[package]"));
+        let app_state = app_state(".", ServePolicy::default());
+        let response = file_handler(
+            State(app_state),
+            Path(path),
+            Query(Default::default()),
+            HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(body_string(response).await.trim().contains("[package]"));
+    }
+
+    async fn get_file(path: &str, request: &[(header::HeaderName, &str)]) -> Response {
+        let app_state = app_state(".", ServePolicy::default());
+        let mut headers = HeaderMap::new();
+        for (name, value) in request {
+            headers.insert(name.clone(), value.parse().unwrap());
+        }
+        file_handler(
+            State(app_state),
+            Path(path.to_string()),
+            Query(Default::default()),
+            headers,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_binary_file() -> anyhow::Result<()> {
+        let response = get_file("fixture/ed25519.sk", &[]).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "32");
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(&body[..], include_bytes!("../../fixture/ed25519.sk"));
+
+        let response = get_file("Cargo.toml", &[]).await;
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/x-toml");
+        let response = get_file("fixture/juventus.xlsx", &[]).await;
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get() {
+        let response = get_file("Cargo.toml", &[]).await;
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let modified = response.headers()[header::LAST_MODIFIED]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let response = get_file("Cargo.toml", &[(header::IF_NONE_MATCH, &etag)]).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let weak = format!("\"other\", W/{}", etag);
+        let response = get_file("Cargo.toml", &[(header::IF_NONE_MATCH, &weak)]).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let response = get_file("Cargo.toml", &[(header::IF_MODIFIED_SINCE, &modified)]).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        // If-None-Match wins over a matching date
+        let request = [
+            (header::IF_NONE_MATCH, "\"stale\""),
+            (header::IF_MODIFIED_SINCE, &modified),
+        ];
+        assert_eq!(
+            get_file("Cargo.toml", &request).await.status(),
+            StatusCode::OK
+        );
+        let request = [(header::IF_MODIFIED_SINCE, "Thu, 01 Jan 1970 00:00:00 GMT")];
+        assert_eq!(
+            get_file("Cargo.toml", &request).await.status(),
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_range_requests() -> anyhow::Result<()> {
+        let content = std::fs::read("Cargo.toml")?;
+        let len = content.len();
+        for (range, start, end) in [
+            ("bytes=0-9", 0, 9),
+            ("bytes=10-", 10, len - 1),
+            ("bytes=-5", len - 5, len - 1),
+            ("bytes=5-100000", 5, len - 1),
+        ] {
+            let response = get_file("Cargo.toml", &[(header::RANGE, range)]).await;
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT, "{}", range);
+            assert_eq!(
+                response.headers()[header::CONTENT_RANGE],
+                format!("bytes {}-{}/{}", start, end, len)
+            );
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+            assert_eq!(&body[..], &content[start..=end]);
+        }
+
+        let unsatisfiable = format!("bytes={}-", len);
+        let response = get_file("Cargo.toml", &[(header::RANGE, &unsatisfiable)]).await;
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            response.headers()[header::CONTENT_RANGE],
+            format!("bytes */{}", len)
+        );
+
+        // multiple ranges and stale If-Range fall back to the full body
+        let response = get_file("Cargo.toml", &[(header::RANGE, "bytes=0-1,4-5")]).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let request = [
+            (header::RANGE, "bytes=0-9"),
+            (header::IF_RANGE, "\"stale\""),
+        ];
+        assert_eq!(
+            get_file("Cargo.toml", &request).await.status(),
+            StatusCode::OK
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dir_listing() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let nested = root.path().join("a b");
+        std::fs::create_dir(&nested)?;
+        std::fs::create_dir(nested.join("sub"))?;
+        std::fs::write(nested.join("<x>&.txt"), "hello")?;
+        std::fs::write(nested.join("big.bin"), vec![0u8; 2048])?;
+        let app_state = app_state(root.path(), ServePolicy::default());
+
+        let (path, headers) = listing_request("a b/", false);
+        let response = file_handler(
+            State(app_state.clone()),
+            path,
+            Query(Default::default()),
+            headers,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let html = body_string(response).await;
+        assert!(html.contains(r#"<a href="/">../</a>"#));
+        assert!(html.contains(r#"<a href="/a%20b/sub/">sub/</a>"#));
+        assert!(html.contains(r#"<a href="/a%20b/%3Cx%3E%26.txt">&lt;x&gt;&amp;.txt</a>"#));
+        assert!(html.contains("2.0 KiB"));
+        assert!(html.contains("<title>Index of /a b/</title>"));
+        assert!(!html.contains("<x>"));
+
+        let (path, headers) = listing_request("a b", true);
+        let query = ListingQuery {
+            sort: SortKey::Size,
+            order: SortOrder::Desc,
+        };
+        let response = file_handler(State(app_state), path, Query(query), headers).await;
+        let listing: serde_json::Value = serde_json::from_str(&body_string(response).await)?;
+        assert_eq!(listing["path"], "/a%20b/");
+        assert_eq!(listing["parent"], "/");
+        let names = listing["entries"]
+            .as_array()
+            .expect("entries")
+            .iter()
+            .map(|e| e["name"].as_str().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["sub", "big.bin", "<x>&.txt"]);
+        assert_eq!(listing["entries"][1]["size"], 2048);
+        assert_eq!(listing["entries"][0]["is_dir"], true);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    /// An `outer` directory holding a secret, with `outer/root` as the served root.
+    fn traversal_fixture() -> anyhow::Result<tempfile::TempDir> {
+        let outer = tempfile::tempdir()?;
+        let root = outer.path().join("root");
+        std::fs::create_dir_all(root.join("a/b"))?;
+        std::fs::create_dir(root.join(".git"))?;
+        std::fs::write(outer.path().join("secret.txt"), "secret")?;
+        std::fs::write(root.join("a/b/public.txt"), "public")?;
+        std::fs::write(root.join(".env"), "TOKEN=1")?;
+        std::fs::write(root.join(".git/config"), "[core]")?;
+        std::os::unix::fs::symlink("../secret.txt", root.join("escape.txt"))?;
+        std::os::unix::fs::symlink(outer.path(), root.join("escape-dir"))?;
+        std::os::unix::fs::symlink("a/b/public.txt", root.join("inside.txt"))?;
+        std::os::unix::fs::symlink(".env", root.join("env-link"))?;
+        Ok(outer)
+    }
+
+    async fn get(state: Arc<AppState>, uri: &str) -> (StatusCode, String) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = router(state).oneshot(request).await.unwrap();
+        let status = response.status();
+        (status, body_string(response).await)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_traversal_payloads() -> anyhow::Result<()> {
+        let outer = traversal_fixture()?;
+        let state = app_state(outer.path().join("root"), ServePolicy::default());
+        assert_eq!(
+            get(state.clone(), "/a/b/public.txt").await,
+            (StatusCode::OK, "public".to_string())
+        );
+        for uri in [
+            "/../secret.txt",
+            "/a/../../secret.txt",
+            "/..%2fsecret.txt",
+            "/%2e%2e/secret.txt",
+            "/%2e%2e%2fsecret.txt",
+            "/a/b/%2e%2e%2f%2e%2e%2f%2e%2e%2fsecret.txt",
+            "/%2E%2E%2Fsecret.txt",
+            "/..%2f..%2f..%2f..%2f..%2f..%2fetc%2fpasswd",
+            "//etc/passwd",
+            "/%2fetc%2fpasswd",
+            "/file/..%2fsecret.txt",
+            "/%252e%252e%252fsecret.txt",
+            "/..%5csecret.txt",
+            "/escape.txt",
+            "/escape-dir/secret.txt",
+            "/escape-dir/",
+        ] {
+            let (status, body) = get(state.clone(), uri).await;
+            assert!(status.is_client_error(), "{} -> {}", uri, status);
+            assert!(!body.contains("secret"), "{} leaked {:?}", uri, body);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_directory_is_served() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        std::fs::create_dir(root.path().join("file"))?;
+        std::fs::write(root.path().join("file/x.txt"), "nested")?;
+        std::fs::write(root.path().join("x.txt"), "top")?;
+        let state = app_state(root.path(), ServePolicy::default());
+        assert_eq!(get(state.clone(), "/file/x.txt").await.1, "nested");
+        assert_eq!(get(state, "/x.txt").await.1, "top");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_policy() -> anyhow::Result<()> {
+        let outer = traversal_fixture()?;
+        let root = outer.path().join("root");
+        let state = app_state(&root, ServePolicy::default());
+        assert_eq!(
+            get(state.clone(), "/escape.txt").await.0,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(get(state.clone(), "/inside.txt").await.1, "public");
+        let (_, listing) = get(state, "/").await;
+        assert!(listing.contains("inside.txt") && !listing.contains("escape"));
+
+        let policy = ServePolicy {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let state = app_state(&root, policy);
+        assert_eq!(get(state.clone(), "/escape.txt").await.1, "secret");
+        // following symlinks never makes `..` acceptable
+        assert!(get(state, "/..%2fsecret.txt").await.0.is_client_error());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_dotfile_policy() -> anyhow::Result<()> {
+        let outer = traversal_fixture()?;
+        let root = outer.path().join("root");
+        for (dotfiles, status) in [
+            (DotfilePolicy::Ignore, StatusCode::NOT_FOUND),
+            (DotfilePolicy::Deny, StatusCode::FORBIDDEN),
+            (DotfilePolicy::Allow, StatusCode::OK),
+        ] {
+            let policy = ServePolicy {
+                dotfiles,
+                ..Default::default()
+            };
+            let state = app_state(&root, policy);
+            for uri in ["/.env", "/%2eenv", "/.git/config", "/env-link"] {
+                assert_eq!(
+                    get(state.clone(), uri).await.0,
+                    status,
+                    "{} {:?}",
+                    uri,
+                    dotfiles
+                );
+            }
+            let (_, listing) = get(state, "/").await;
+            assert_eq!(listing.contains(".env"), dotfiles == DotfilePolicy::Allow);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rustls_config() {
+        let addr = SocketAddr::from(([0, 0, 0, 0], 0));
+        assert!(rustls_config(&TlsSource::SelfSigned, addr).await.is_ok());
+
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = (dir.path().join("cert.pem"), dir.path().join("key.pem"));
+        let missing = TlsSource::Pem {
+            cert: cert.clone(),
+            key: key.clone(),
+        };
+        assert!(matches!(
+            rustls_config(&missing, addr).await,
+            Err(Error::Io(_))
+        ));
+
+        std::fs::write(&cert, "not a certificate").unwrap();
+        std::fs::write(&key, "not a key").unwrap();
+        let garbage = TlsSource::Pem { cert, key };
+        assert!(matches!(
+            rustls_config(&garbage, addr).await,
+            Err(Error::Crypto(CryptoError::Tls(_)))
+        ));
     }
 }
@@ -1,20 +1,21 @@
-use anyhow::Result;
+use crate::{Error, Result};
 use fancy_duration::ParseFancyDuration;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    aud: String,
-    exp: u64,
+pub struct Claims {
+    pub sub: String,
+    pub aud: String,
+    pub exp: u64,
 }
 
 pub const SECRET_KEY: &[u8; 6] = b"secret";
 
 pub fn process_jwt_sign(sub: String, aud: String, exp: String) -> Result<String> {
-    let exp = Duration::parse_fancy_duration(exp)?;
+    let exp = Duration::parse_fancy_duration(exp)
+        .map_err(|e| Error::invalid_input(format!("Invalid expiry: {}", e)))?;
     let exp = jsonwebtoken::get_current_timestamp() + exp.as_secs();
 
     let my_claims = Claims { sub, aud, exp };
@@ -28,13 +29,12 @@ pub fn process_jwt_sign(sub: String, aud: String, exp: String) -> Result<String>
     Ok(token)
 }
 
-pub fn process_jwt_verify(token: String) -> Result<bool> {
+/// Verify the token signature and expiry, returning its header and claims.
+pub fn process_jwt_verify(token: String) -> Result<TokenData<Claims>> {
     let token_data = decode::<Claims>(
         &token,
         &DecodingKey::from_secret(SECRET_KEY),
         &Validation::default(),
     )?;
-    println!("{:?}", token_data.claims);
-    println!("{:?}", token_data.header);
-    Ok(true)
+    Ok(token_data)
 }
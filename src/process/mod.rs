@@ -1,19 +1,51 @@
 mod b64;
 mod chacha;
+mod codec;
+mod convert;
 mod csv_convert;
+mod csv_ops;
+mod csv_stats;
+mod csv_validate;
+mod diff;
 mod gen_pass;
+mod gen_token;
 mod http_serve;
 mod jwt;
+mod otp;
+mod password_check;
+mod query;
+mod schema;
 mod text;
 
 pub use b64::process_decode;
 pub use b64::process_encode;
 pub use chacha::{process_chacha_generate, process_decrypt, process_encrypt};
-pub use csv_convert::process_csv;
-pub use gen_pass::process_genpass;
-pub use http_serve::process_http_serve;
+pub use codec::{process_codec_decode, process_codec_encode};
+pub use convert::process_convert;
+pub use csv_convert::{process_csv, write_records, ReadOptions, Records};
+pub use csv_ops::{process_csv_concat, process_csv_dedupe, process_csv_join};
+pub use csv_stats::{process_csv_stats, ColumnStats, TopValue};
+pub use csv_validate::{
+    process_csv_validate, ColumnSchema, ColumnType, CsvSchema, ValidationReport, Violation,
+};
+pub use diff::{json_patch, process_diff, DiffEntry, DiffOp};
+pub use gen_pass::{
+    process_genpass, process_genphrase, CrackTimes, GeneratedPassword, PassphrasePolicy,
+    PasswordPolicy, Strength,
+};
+pub use gen_token::{
+    process_gen_nanoid, process_gen_token, process_gen_ulid, process_gen_uuid, NANOID_ALPHABET,
+};
+pub use http_serve::{process_http_serve, ServePolicy, TlsSource};
 pub use jwt::process_jwt_sign;
-pub use jwt::process_jwt_verify;
+pub use jwt::{process_jwt_verify, Claims};
+pub use otp::{
+    process_hotp, process_otp_secret, process_otp_uri, process_totp, process_totp_verify,
+    OtpParams, MAX_WINDOW,
+};
+pub use password_check::{process_password_check, AuditSummary, PasswordAudit, PasswordReport};
+pub use query::{process_query, JsonPath};
+pub use schema::{process_schema_infer, process_schema_validate, SchemaError};
 pub use text::process_generate;
 pub use text::process_sign;
 pub use text::process_verify;
@@ -0,0 +1,285 @@
+use std::{
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Error, KeyError, Result};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::{rngs::OsRng, RngCore};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+use crate::cli::OtpAlgorithm;
+
+/// Largest accepted verification window, in time steps on each side of now.
+pub const MAX_WINDOW: u64 = 10;
+
+/// Parameters shared by code generation, verification and provisioning URIs.
+#[derive(Debug, Clone, Copy)]
+pub struct OtpParams {
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    /// TOTP time step in seconds.
+    pub period: u64,
+}
+
+impl Default for OtpParams {
+    fn default() -> Self {
+        Self {
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+        }
+    }
+}
+
+impl OtpParams {
+    fn validate(&self) -> Result<()> {
+        if !(6..=8).contains(&self.digits) {
+            return Err(Error::invalid_input("Digits must be between 6 and 8"));
+        }
+        if self.period == 0 {
+            return Err(Error::invalid_input("Period must be at least one second"));
+        }
+        Ok(())
+    }
+}
+
+/// Generate a random secret of `bytes` bytes, base32 encoded without padding.
+pub fn process_otp_secret(bytes: usize) -> Result<String> {
+    if !(10..=64).contains(&bytes) {
+        return Err(Error::invalid_input(
+            "Secret size must be between 10 and 64 bytes",
+        ));
+    }
+    let mut secret = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut secret);
+    Ok(BASE32_NOPAD.encode(&secret))
+}
+
+/// Build an `otpauth://` URI authenticator apps can import, e.g. from a QR code.
+/// With `counter` set the URI describes an HOTP instead of a TOTP token.
+pub fn process_otp_uri(
+    secret: &str,
+    account: &str,
+    issuer: Option<&str>,
+    params: OtpParams,
+    counter: Option<u64>,
+) -> Result<String> {
+    params.validate()?;
+    decode_secret(secret)?;
+    let encode = |s: &str| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string();
+    let label = match issuer {
+        Some(issuer) => format!("{}:{}", encode(issuer), encode(account)),
+        None => encode(account),
+    };
+    let kind = if counter.is_some() { "hotp" } else { "totp" };
+    let mut uri = format!(
+        "otpauth://{}/{}?secret={}&algorithm={}&digits={}",
+        kind,
+        label,
+        normalize_secret(secret),
+        params.algorithm.to_string().to_uppercase(),
+        params.digits
+    );
+    match counter {
+        Some(counter) => uri.push_str(&format!("&counter={}", counter)),
+        None => uri.push_str(&format!("&period={}", params.period)),
+    }
+    if let Some(issuer) = issuer {
+        uri.push_str(&format!("&issuer={}", encode(issuer)));
+    }
+    Ok(uri)
+}
+
+/// RFC 4226 HOTP code for `counter`.
+pub fn process_hotp(secret: &str, counter: u64, params: OtpParams) -> Result<String> {
+    params.validate()?;
+    let key = decode_secret(secret)?;
+    hotp(&key, counter, params)
+}
+
+/// RFC 6238 TOTP code for `time` (unix seconds), or now when omitted.
+pub fn process_totp(secret: &str, time: Option<u64>, params: OtpParams) -> Result<String> {
+    let time = match time {
+        Some(time) => time,
+        None => now()?,
+    };
+    process_hotp(secret, time / params.period.max(1), params)
+}
+
+/// Check `code` against the TOTP codes within `window` steps of `time`.
+/// Returns the matching step offset, so callers can report clock drift.
+pub fn process_totp_verify(
+    secret: &str,
+    code: &str,
+    time: Option<u64>,
+    window: u64,
+    params: OtpParams,
+) -> Result<Option<i64>> {
+    params.validate()?;
+    if window > MAX_WINDOW {
+        return Err(Error::invalid_input(format!(
+            "Window must be between 0 and {}",
+            MAX_WINDOW
+        )));
+    }
+    let key = decode_secret(secret)?;
+    let time = match time {
+        Some(time) => time,
+        None => now()?,
+    };
+    let step = time / params.period;
+    let window = window as i64;
+    let code = code.trim();
+    let mut matched = None;
+    // compare every candidate so timing does not reveal which step matched
+    for offset in -window..=window {
+        let Some(counter) = step.checked_add_signed(offset) else {
+            continue;
+        };
+        let candidate = hotp(&key, counter, params)?;
+        if constant_time_eq(candidate.as_bytes(), code.as_bytes()) && matched.is_none() {
+            matched = Some(offset);
+        }
+    }
+    Ok(matched)
+}
+
+fn hotp(key: &[u8], counter: u64, params: OtpParams) -> Result<String> {
+    let counter = counter.to_be_bytes();
+    let digest = match params.algorithm {
+        OtpAlgorithm::Sha1 => hmac::<Hmac<Sha1>>(key, &counter)?,
+        OtpAlgorithm::Sha256 => hmac::<Hmac<Sha256>>(key, &counter)?,
+        OtpAlgorithm::Sha512 => hmac::<Hmac<Sha512>>(key, &counter)?,
+    };
+    // dynamic truncation, RFC 4226 section 5.3
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    let code = binary % 10u32.pow(params.digits);
+    Ok(format!("{:0width$}", code, width = params.digits as usize))
+}
+
+fn hmac<M: Mac + hmac::digest::KeyInit>(key: &[u8], msg: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = <M as Mac>::new_from_slice(key).map_err(|e| KeyError::Invalid(e.to_string()))?;
+    mac.update(msg);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// Secrets are often shown lowercase, grouped with spaces or padded.
+fn normalize_secret(secret: &str) -> String {
+    secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn decode_secret(secret: &str) -> Result<Vec<u8>> {
+    let key = BASE32_NOPAD
+        .decode(normalize_secret(secret).as_bytes())
+        .map_err(|e| KeyError::Invalid(format!("invalid base32 secret: {}", e)))?;
+    if key.is_empty() {
+        return Err(KeyError::Invalid("secret is empty".to_string()).into());
+    }
+    Ok(key)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(io::Error::other)?
+        .as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(algorithm: OtpAlgorithm, digits: u32) -> OtpParams {
+        OtpParams {
+            algorithm,
+            digits,
+            period: 30,
+        }
+    }
+
+    #[test]
+    fn test_hotp_rfc4226() -> Result<()> {
+        let secret = BASE32_NOPAD.encode(b"12345678901234567890");
+        let codes = ["755224", "287082", "359152", "969429", "338314"];
+        for (counter, code) in codes.iter().enumerate() {
+            assert_eq!(
+                process_hotp(&secret, counter as u64, OtpParams::default())?,
+                *code
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_totp_rfc6238() -> Result<()> {
+        let vectors = [
+            (OtpAlgorithm::Sha1, &b"12345678901234567890"[..], "94287082"),
+            (
+                OtpAlgorithm::Sha256,
+                &b"12345678901234567890123456789012"[..],
+                "46119246",
+            ),
+            (
+                OtpAlgorithm::Sha512,
+                &b"1234567890123456789012345678901234567890123456789012345678901234"[..],
+                "90693936",
+            ),
+        ];
+        for (algorithm, key, code) in vectors {
+            let secret = BASE32_NOPAD.encode(key);
+            assert_eq!(process_totp(&secret, Some(59), params(algorithm, 8))?, code);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_totp_verify_window() -> Result<()> {
+        let secret = process_otp_secret(20)?.to_lowercase();
+        let params = OtpParams::default();
+        let code = process_totp(&secret, Some(1_000_000), params)?;
+        let verify = |time, window| process_totp_verify(&secret, &code, Some(time), window, params);
+        assert_eq!(verify(1_000_000, 0)?, Some(0));
+        assert_eq!(verify(1_000_030, 1)?, Some(-1));
+        assert_eq!(verify(1_000_030, 0)?, None);
+        assert_eq!(verify(1_000_090, 1)?, None);
+        assert!(verify(1_000_000, MAX_WINDOW + 1).is_err());
+        assert!(verify(1_000_000, u64::MAX).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_otp_uri() -> Result<()> {
+        let uri = process_otp_uri(
+            "jbsw y3dp ehpk 3pxp",
+            "alice@example.com",
+            Some("ACME Co"),
+            OtpParams::default(),
+            None,
+        )?;
+        assert_eq!(
+            uri,
+            "otpauth://totp/ACME%20Co:alice%40example%2Ecom?secret=JBSWY3DPEHPK3PXP\
+             &algorithm=SHA1&digits=6&period=30&issuer=ACME%20Co"
+        );
+        assert!(process_otp_uri("not base32!", "a", None, OtpParams::default(), None).is_err());
+        Ok(())
+    }
+}
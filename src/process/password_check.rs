@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+};
+
+use crate::{Error, Result};
+use serde::Serialize;
+use zxcvbn::zxcvbn;
+
+use super::gen_pass::Strength;
+use crate::utils::{get_content, get_reader};
+
+/// Strength of the password found on one input line (1-based).
+#[derive(Debug, Serialize)]
+pub struct PasswordReport {
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(flatten)]
+    pub strength: Strength,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AuditSummary {
+    pub total: usize,
+    pub blank: usize,
+    /// Passwords appearing on more than one line, counted once per extra occurrence.
+    pub reused: usize,
+    /// Number of passwords per zxcvbn score, index 0 to 4.
+    pub scores: [usize; 5],
+    pub below_min_score: usize,
+    pub min_score: u8,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasswordAudit {
+    pub passwords: Vec<PasswordReport>,
+    pub summary: AuditSummary,
+}
+
+/// Score every non-blank line of `input`. `user_inputs` are extra words (user
+/// names, company names...) zxcvbn should treat as easily guessed; words from
+/// `dictionary`, one per line, are added to them.
+pub fn process_password_check(
+    input: &str,
+    user_inputs: &[String],
+    dictionary: Option<&str>,
+    min_score: u8,
+    reveal: bool,
+) -> Result<PasswordAudit> {
+    let mut words = user_inputs.to_vec();
+    if let Some(dictionary) = dictionary {
+        let content = String::from_utf8(get_content(dictionary)?)?;
+        words.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|w| !w.is_empty())
+                .map(String::from),
+        );
+    }
+    let words = words.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut summary = AuditSummary {
+        min_score,
+        ..Default::default()
+    };
+    let mut seen = HashMap::new();
+    let mut passwords = Vec::new();
+    let mut reader = BufReader::new(get_reader(input)?);
+    let mut buf = Vec::new();
+    for idx in 0.. {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        // a stray non-UTF-8 line is still scored rather than failing the whole audit
+        let line = String::from_utf8_lossy(&buf);
+        let password = line.trim_end_matches('\n');
+        let password = password.strip_suffix('\r').unwrap_or(password);
+        if password.is_empty() {
+            summary.blank += 1;
+            continue;
+        }
+        summary.total += 1;
+        let occurrences = seen.entry(password.to_string()).or_insert(0);
+        *occurrences += 1;
+        if *occurrences > 1 {
+            summary.reused += 1;
+        }
+
+        let estimate = zxcvbn(password, &words).map_err(|e| Error::invalid_input(e.to_string()))?;
+        let strength = Strength::from(&estimate);
+        summary.scores[strength.score as usize] += 1;
+        if strength.score < min_score {
+            summary.below_min_score += 1;
+        }
+        passwords.push(PasswordReport {
+            line: idx + 1,
+            password: reveal.then(|| password.to_string()),
+            strength,
+        });
+    }
+    Ok(PasswordAudit { passwords, summary })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_check() -> Result<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        std::fs::write(
+            file.path(),
+            b"password\r\n\nJuventus1897!\ncorrect horse battery staple\npassword\npass\xffword",
+        )?;
+        let input = file.path().to_string_lossy();
+        let plain = process_password_check(&input, &[], None, 3, false)?;
+        let audited = process_password_check(&input, &["juventus".to_string()], None, 3, true)?;
+
+        assert_eq!(plain.summary.total, 5);
+        assert_eq!(plain.summary.blank, 1);
+        assert_eq!(plain.summary.reused, 1);
+        assert_eq!(plain.summary.scores[0], 2);
+        assert_eq!(plain.passwords[1].line, 3);
+        assert!(plain.passwords[0].password.is_none());
+
+        assert_eq!(
+            audited.passwords[1].password.as_deref(),
+            Some("Juventus1897!")
+        );
+        assert!(audited.passwords[1].strength.guesses < plain.passwords[1].strength.guesses);
+        assert_eq!(audited.passwords[4].line, 6);
+        assert_eq!(
+            audited.passwords[4].password.as_deref(),
+            Some("pass\u{fffd}word")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,689 @@
+use crate::{Error, Result};
+use regex::Regex;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+use super::convert::{read_input, write_documents};
+use crate::cli::DataFormat;
+
+/// A parsed JSONPath expression such as `$.players[?(@.position == "Goalkeeper")].name`.
+#[derive(Debug)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug)]
+enum Segment {
+    Child(Selector),
+    Descendant(Selector),
+}
+
+#[derive(Debug)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Union(Vec<Selector>),
+    Filter(Box<Expr>),
+}
+
+#[derive(Debug)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    Exists(Operand),
+}
+
+#[derive(Debug)]
+enum Operand {
+    Current(Vec<Segment>),
+    Root(Vec<Segment>),
+    Literal(Value),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+}
+
+/// How deeply filters, groups and negations may nest. Evaluating a filter
+/// recurses into `select`, so this also bounds the recursion at query time.
+const MAX_NESTING: usize = 32;
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+pub fn process_query(
+    query: &str,
+    input: &str,
+    from: Option<DataFormat>,
+    to: DataFormat,
+    pretty: bool,
+) -> Result<String> {
+    let path = JsonPath::parse(query)?;
+    let docs = read_input(input, from)?;
+    let root = match docs.len() {
+        1 => docs.into_iter().next().expect("one document"),
+        _ => Value::Array(docs),
+    };
+    let results = Value::Array(path.query(&root).into_iter().cloned().collect());
+    let doc = match to {
+        // a TOML document must be a table
+        DataFormat::Toml => serde_json::json!({ "results": results }),
+        _ => results,
+    };
+    write_documents(&[doc], to, pretty)
+}
+
+impl JsonPath {
+    pub fn parse(query: &str) -> Result<Self> {
+        let query = query.trim();
+        let query = if query.starts_with('.') || query.starts_with('[') {
+            format!("${}", query)
+        } else {
+            query.to_string()
+        };
+        let mut parser = Parser::new(&query);
+        if !parser.eat('$') {
+            return Err(parser.error("expected '$'"));
+        }
+        let segments = parser.segments()?;
+        parser.skip_ws();
+        if parser.peek().is_some() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn query<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        select(&self.segments, root, root)
+    }
+}
+
+fn select<'a>(segments: &[Segment], node: &'a Value, root: &'a Value) -> Vec<&'a Value> {
+    let mut nodes = vec![node];
+    for segment in segments {
+        let mut next = Vec::new();
+        for node in nodes {
+            match segment {
+                Segment::Child(selector) => selector.apply(node, root, &mut next),
+                Segment::Descendant(selector) => {
+                    for node in descendants(node) {
+                        selector.apply(node, root, &mut next);
+                    }
+                }
+            }
+        }
+        nodes = next;
+    }
+    nodes
+}
+
+fn descendants(node: &Value) -> Vec<&Value> {
+    let mut ret = vec![node];
+    let mut idx = 0;
+    while idx < ret.len() {
+        match ret[idx] {
+            Value::Array(items) => ret.extend(items.iter()),
+            Value::Object(map) => ret.extend(map.values()),
+            _ => {}
+        }
+        idx += 1;
+    }
+    ret
+}
+
+fn children(node: &Value) -> Vec<&Value> {
+    match node {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(map) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Selector {
+    fn apply<'a>(&self, node: &'a Value, root: &'a Value, out: &mut Vec<&'a Value>) {
+        match self {
+            Selector::Name(name) => out.extend(node.get(name)),
+            Selector::Wildcard => out.extend(children(node)),
+            Selector::Index(idx) => {
+                if let Value::Array(items) = node {
+                    let idx = if *idx < 0 {
+                        items.len() as i64 + idx
+                    } else {
+                        *idx
+                    };
+                    if idx >= 0 {
+                        out.extend(items.get(idx as usize));
+                    }
+                }
+            }
+            Selector::Slice(start, end, step) => {
+                if let Value::Array(items) = node {
+                    out.extend(slice(items, *start, *end, *step));
+                }
+            }
+            Selector::Union(selectors) => {
+                for selector in selectors {
+                    selector.apply(node, root, out);
+                }
+            }
+            Selector::Filter(expr) => {
+                out.extend(
+                    children(node)
+                        .into_iter()
+                        .filter(|child| expr.eval(child, root)),
+                );
+            }
+        }
+    }
+}
+
+fn slice(items: &[Value], start: Option<i64>, end: Option<i64>, step: Option<i64>) -> Vec<&Value> {
+    let len = items.len() as i64;
+    let step = step.unwrap_or(1);
+    let normalize = |idx: i64| if idx < 0 { len + idx } else { idx };
+    let mut ret = Vec::new();
+    if step > 0 {
+        let start = start.map_or(0, normalize).clamp(0, len);
+        let end = end.map_or(len, normalize).clamp(0, len);
+        let mut idx = start;
+        while idx < end {
+            ret.push(&items[idx as usize]);
+            match idx.checked_add(step) {
+                Some(next) => idx = next,
+                None => break,
+            }
+        }
+    } else if step < 0 {
+        let start = start.map_or(len - 1, normalize).clamp(-1, len - 1);
+        let end = end.map_or(-1, normalize).clamp(-1, len - 1);
+        let mut idx = start;
+        while idx > end {
+            ret.push(&items[idx as usize]);
+            match idx.checked_add(step) {
+                Some(next) => idx = next,
+                None => break,
+            }
+        }
+    }
+    ret
+}
+
+impl Expr {
+    fn eval(&self, current: &Value, root: &Value) -> bool {
+        match self {
+            Expr::Or(a, b) => a.eval(current, root) || b.eval(current, root),
+            Expr::And(a, b) => a.eval(current, root) && b.eval(current, root),
+            Expr::Not(expr) => !expr.eval(current, root),
+            Expr::Exists(operand) => operand.resolve(current, root).is_some(),
+            Expr::Compare(left, op, right) => {
+                let left = left.resolve(current, root);
+                if let (CompareOp::Match, Operand::Regex(re)) = (op, right) {
+                    return matches!(left, Some(Value::String(s)) if re.is_match(s));
+                }
+                let right = right.resolve(current, root);
+                let ordering = match (left, right) {
+                    (Some(a), Some(b)) => compare(a, b),
+                    (None, None) => Some(Ordering::Equal),
+                    _ => None,
+                };
+                match op {
+                    CompareOp::Eq => ordering == Some(Ordering::Equal),
+                    CompareOp::Ne => ordering != Some(Ordering::Equal),
+                    CompareOp::Lt => ordering == Some(Ordering::Less),
+                    CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    CompareOp::Gt => ordering == Some(Ordering::Greater),
+                    CompareOp::Ge => {
+                        matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                    }
+                    CompareOp::Match => false,
+                }
+            }
+        }
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (a, b) if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, current: &'a Value, root: &'a Value) -> Option<&'a Value> {
+        match self {
+            Operand::Current(segments) => select(segments, current, root).into_iter().next(),
+            Operand::Root(segments) => select(segments, root, root).into_iter().next(),
+            Operand::Literal(value) => Some(value),
+            Operand::Regex(_) => None,
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::invalid_input(format!(
+            "Invalid query at position {}: {}",
+            self.pos, message
+        ))
+    }
+
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth == MAX_NESTING {
+            return Err(self.error(&format!("nested deeper than {} levels", MAX_NESTING)));
+        }
+        self.depth += 1;
+        let ret = parse(self);
+        self.depth -= 1;
+        ret
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        self.skip_ws();
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>> {
+        let mut segments = Vec::new();
+        loop {
+            if self.eat_str("..") {
+                let selector = match self.peek() {
+                    Some('[') => self.bracket()?,
+                    _ => self.dot_selector()?,
+                };
+                segments.push(Segment::Descendant(selector));
+            } else if self.eat('.') {
+                segments.push(Segment::Child(self.dot_selector()?));
+            } else if self.peek() == Some('[') {
+                segments.push(Segment::Child(self.bracket()?));
+            } else {
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn dot_selector(&mut self) -> Result<Selector> {
+        if self.eat('*') {
+            return Ok(Selector::Wildcard);
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '$')
+        {
+            self.bump();
+        }
+        if start == self.pos {
+            return Err(self.error("expected a member name"));
+        }
+        Ok(Selector::Name(self.input[start..self.pos].to_string()))
+    }
+
+    fn bracket(&mut self) -> Result<Selector> {
+        self.expect('[')?;
+        self.skip_ws();
+        let selector = if self.eat('?') {
+            Selector::Filter(Box::new(self.nested(Self::or_expr)?))
+        } else if self.eat('*') {
+            Selector::Wildcard
+        } else {
+            let mut selectors = vec![self.union_item()?];
+            self.skip_ws();
+            while self.eat(',') {
+                selectors.push(self.union_item()?);
+                self.skip_ws();
+            }
+            if selectors.len() == 1 {
+                selectors.remove(0)
+            } else {
+                Selector::Union(selectors)
+            }
+        };
+        self.expect(']')?;
+        Ok(selector)
+    }
+
+    fn union_item(&mut self) -> Result<Selector> {
+        self.skip_ws();
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.string()?)),
+            _ => {
+                let start = self.integer()?;
+                self.skip_ws();
+                if !self.eat(':') {
+                    return start
+                        .map(Selector::Index)
+                        .ok_or_else(|| self.error("expected an index, name or slice"));
+                }
+                self.skip_ws();
+                let end = self.integer()?;
+                self.skip_ws();
+                let step = if self.eat(':') {
+                    self.skip_ws();
+                    self.integer()?
+                } else {
+                    None
+                };
+                Ok(Selector::Slice(start, end, step))
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<Option<i64>> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        match &self.input[start..self.pos] {
+            "" => Ok(None),
+            s => s
+                .parse()
+                .map(Some)
+                .map_err(|_| self.error("invalid integer")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let quote = self.bump().ok_or_else(|| self.error("expected a string"))?;
+        let mut ret = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('\\') => match self.bump() {
+                    Some('n') => ret.push('\n'),
+                    Some('t') => ret.push('\t'),
+                    Some(c) => ret.push(c),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some(c) if c == quote => return Ok(ret),
+                Some(c) => ret.push(c),
+            }
+        }
+    }
+
+    fn regex(&mut self) -> Result<String> {
+        self.bump();
+        let mut ret = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated regex")),
+                Some('\\') if self.peek() == Some('/') => ret.push(self.bump().unwrap_or('/')),
+                Some('/') => return Ok(ret),
+                Some(c) => ret.push(c),
+            }
+        }
+    }
+
+    fn or_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.and_expr()?;
+        loop {
+            self.skip_ws();
+            if !self.eat_str("||") {
+                return Ok(expr);
+            }
+            expr = Expr::Or(Box::new(expr), Box::new(self.and_expr()?));
+        }
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.unary_expr()?;
+        loop {
+            self.skip_ws();
+            if !self.eat_str("&&") {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.unary_expr()?));
+        }
+    }
+
+    fn unary_expr(&mut self) -> Result<Expr> {
+        self.skip_ws();
+        if self.rest().starts_with('!') && !self.rest().starts_with("!=") {
+            self.bump();
+            return Ok(Expr::Not(Box::new(self.nested(Self::unary_expr)?)));
+        }
+        if self.eat('(') {
+            let expr = self.nested(Self::or_expr)?;
+            self.expect(')')?;
+            return Ok(expr);
+        }
+        let left = self.operand()?;
+        self.skip_ws();
+        let op = if self.eat_str("==") {
+            CompareOp::Eq
+        } else if self.eat_str("!=") {
+            CompareOp::Ne
+        } else if self.eat_str("<=") {
+            CompareOp::Le
+        } else if self.eat_str(">=") {
+            CompareOp::Ge
+        } else if self.eat_str("=~") {
+            CompareOp::Match
+        } else if self.eat('<') {
+            CompareOp::Lt
+        } else if self.eat('>') {
+            CompareOp::Gt
+        } else {
+            return Ok(Expr::Exists(left));
+        };
+        self.skip_ws();
+        let right = self.operand()?;
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        self.skip_ws();
+        match self.peek() {
+            Some('@') => {
+                self.bump();
+                Ok(Operand::Current(self.segments()?))
+            }
+            Some('$') => {
+                self.bump();
+                Ok(Operand::Root(self.segments()?))
+            }
+            Some('\'' | '"') => Ok(Operand::Literal(Value::String(self.string()?))),
+            Some('/') => {
+                let pattern = self.regex()?;
+                let re = Regex::new(&pattern).map_err(|e| self.error(&e.to_string()))?;
+                Ok(Operand::Regex(re))
+            }
+            _ => {
+                for (word, value) in [
+                    ("true", Value::Bool(true)),
+                    ("false", Value::Bool(false)),
+                    ("null", Value::Null),
+                ] {
+                    if self.eat_str(word) {
+                        return Ok(Operand::Literal(value));
+                    }
+                }
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    self.bump();
+                }
+                let number =
+                    serde_json::from_str::<serde_json::Number>(&self.input[start..self.pos])
+                        .map_err(|_| self.error("expected an operand"))?;
+                Ok(Operand::Literal(Value::Number(number)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn data() -> Value {
+        json!({
+            "players": [
+                {"name": "Szczesny", "position": "Goalkeeper", "kit": 1},
+                {"name": "Perin", "position": "Goalkeeper", "kit": 37},
+                {"name": "Bonucci", "position": "Centre-Back", "kit": 19},
+                {"name": "Dybala", "position": "Second Striker", "kit": 10, "captain": false}
+            ],
+            "club": {"name": "Juventus", "founded": 1897}
+        })
+    }
+
+    fn query(path: &str) -> Vec<Value> {
+        let data = data();
+        JsonPath::parse(path)
+            .unwrap()
+            .query(&data)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_query_paths() {
+        assert_eq!(query("$.club.name"), vec![json!("Juventus")]);
+        assert_eq!(query("$['club']['founded']"), vec![json!(1897)]);
+        assert_eq!(query("$.players[-1].name"), vec![json!("Dybala")]);
+        assert_eq!(query("$.players[0,2].kit"), vec![json!(1), json!(19)]);
+        assert_eq!(query("$.players[1:3].kit"), vec![json!(37), json!(19)]);
+        assert_eq!(query("$.players[::-2].kit"), vec![json!(10), json!(37)]);
+        assert_eq!(query("$.players[*].kit").len(), 4);
+        assert_eq!(query("$..name").len(), 5);
+        assert_eq!(query(".club.*").len(), 2);
+    }
+
+    #[test]
+    fn test_query_huge_step() {
+        let max = i64::MAX;
+        let min = i64::MIN;
+        assert_eq!(
+            query(&format!("$.players[1:4:{}].kit", max)),
+            vec![json!(37)]
+        );
+        assert_eq!(query(&format!("$.players[::{}].kit", min)), vec![json!(10)]);
+        assert_eq!(
+            query(&format!("$.players[{}:{}:{}].kit", max, min, min)),
+            vec![json!(10)]
+        );
+    }
+
+    #[test]
+    fn test_query_filters() {
+        assert_eq!(
+            query(r#"$.players[?(@.position=="Goalkeeper")].name"#),
+            vec![json!("Szczesny"), json!("Perin")]
+        );
+        assert_eq!(
+            query("$.players[?(@.kit > 5 && @.kit < 20)].name"),
+            vec![json!("Bonucci"), json!("Dybala")]
+        );
+        assert_eq!(
+            query("$.players[?(@.captain == false || @.kit == 1)].name"),
+            vec![json!("Szczesny"), json!("Dybala")]
+        );
+        assert_eq!(query("$.players[?(@.captain)].name"), vec![json!("Dybala")]);
+        assert_eq!(query("$.players[?(!@.captain)]").len(), 3);
+        assert_eq!(
+            query(r"$.players[?(@.name =~ /^B\w+i$/)].kit"),
+            vec![json!(19)]
+        );
+        assert_eq!(
+            query("$.players[?(@.kit == $.players[0].kit)].name"),
+            vec![json!("Szczesny")]
+        );
+    }
+
+    #[test]
+    fn test_query_to_toml() -> Result<()> {
+        let ret = process_query(
+            "$.package.name",
+            "Cargo.toml",
+            None,
+            DataFormat::Toml,
+            false,
+        )?;
+        assert_eq!(ret.trim(), r#"results = ["rcli"]"#);
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_invalid() {
+        assert!(JsonPath::parse("players").is_err());
+        assert!(JsonPath::parse("$.players[").is_err());
+        assert!(JsonPath::parse("$.players[?(@.kit ==)]").is_err());
+        let deep = format!("$[?({}@.kit)]", "!".repeat(MAX_NESTING));
+        assert!(JsonPath::parse(&deep).is_err());
+        let deep = format!("$..a[?{}@.kit{}]", "(".repeat(100_000), ")".repeat(100_000));
+        assert!(JsonPath::parse(&deep).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use super::convert::read_input;
+use crate::cli::DataFormat;
+
+const DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A schema violation; `path` is a JSON Pointer to the offending node.
+#[derive(Debug, Serialize)]
+pub struct SchemaError {
+    pub document: usize,
+    pub path: String,
+    pub message: String,
+}
+
+/// Shape accumulated over every sample value seen at one location.
+#[derive(Debug, Default)]
+struct Shape {
+    types: Vec<&'static str>,
+    objects: usize,
+    properties: Vec<(String, Shape, usize)>,
+    items: Option<Box<Shape>>,
+}
+
+pub fn process_schema_validate(
+    schema: &str,
+    input: &str,
+    from: Option<DataFormat>,
+) -> Result<Vec<SchemaError>> {
+    let schema = read_input(schema, None)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::invalid_format("Schema file is empty"))?;
+    let validator = jsonschema::draft202012::new(&schema)
+        .map_err(|e| Error::invalid_format(format!("Invalid schema: {}", e)))?;
+
+    let mut errors = Vec::new();
+    for (document, instance) in read_input(input, from)?.iter().enumerate() {
+        for error in validator.iter_errors(instance) {
+            errors.push(SchemaError {
+                document,
+                path: error.instance_path.to_string(),
+                message: error.to_string(),
+            });
+        }
+    }
+    Ok(errors)
+}
+
+pub fn process_schema_infer(inputs: &[String], from: Option<DataFormat>) -> Result<Value> {
+    let mut shape = Shape::default();
+    for input in inputs {
+        for doc in read_input(input, from)? {
+            shape.add(&doc);
+        }
+    }
+    let mut schema = Map::new();
+    schema.insert("$schema".to_string(), json!(DRAFT_2020_12));
+    if let Value::Object(inferred) = shape.to_schema() {
+        schema.extend(inferred);
+    }
+    Ok(Value::Object(schema))
+}
+
+impl Shape {
+    fn add(&mut self, value: &Value) {
+        let ty = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        if !self.types.contains(&ty) {
+            self.types.push(ty);
+        }
+
+        match value {
+            Value::Object(map) => {
+                self.objects += 1;
+                for (key, value) in map {
+                    let idx = match self.properties.iter().position(|(k, _, _)| k == key) {
+                        Some(idx) => idx,
+                        None => {
+                            self.properties.push((key.clone(), Shape::default(), 0));
+                            self.properties.len() - 1
+                        }
+                    };
+                    let (_, shape, count) = &mut self.properties[idx];
+                    shape.add(value);
+                    *count += 1;
+                }
+            }
+            Value::Array(items) => {
+                let shape = self.items.get_or_insert_with(Default::default);
+                for item in items {
+                    shape.add(item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn to_schema(&self) -> Value {
+        let mut schema = Map::new();
+        let types = self
+            .types
+            .iter()
+            .filter(|&&ty| !(ty == "integer" && self.types.contains(&"number")))
+            .collect::<Vec<_>>();
+        match types.as_slice() {
+            [] => {}
+            [ty] => {
+                schema.insert("type".to_string(), json!(ty));
+            }
+            types => {
+                schema.insert("type".to_string(), json!(types));
+            }
+        }
+
+        if self.objects > 0 {
+            let properties = self
+                .properties
+                .iter()
+                .map(|(key, shape, _)| (key.clone(), shape.to_schema()))
+                .collect::<Map<_, _>>();
+            let required = self
+                .properties
+                .iter()
+                .filter(|(_, _, count)| *count == self.objects)
+                .map(|(key, _, _)| json!(key))
+                .collect::<Vec<_>>();
+            schema.insert("properties".to_string(), Value::Object(properties));
+            if !required.is_empty() {
+                schema.insert("required".to_string(), Value::Array(required));
+            }
+        }
+        if let Some(items) = &self.items {
+            if !items.types.is_empty() {
+                schema.insert("items".to_string(), items.to_schema());
+            }
+        }
+        Value::Object(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape_to_schema() {
+        let mut shape = Shape::default();
+        shape.add(&json!({"name": "Perin", "kit": 37, "tags": ["gk"]}));
+        shape.add(&json!({"name": "Dybala", "kit": 10.5, "captain": null}));
+        assert_eq!(
+            shape.to_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "kit": {"type": "number"},
+                    "tags": {"type": "array", "items": {"type": "string"}},
+                    "captain": {"type": "null"}
+                },
+                "required": ["name", "kit"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_infer_and_validate_csv() -> Result<()> {
+        let schema = process_schema_infer(&["assets/juventus.csv".to_string()], None)?;
+        assert_eq!(schema["$schema"], DRAFT_2020_12);
+        assert_eq!(schema["type"], "array");
+        assert_eq!(
+            schema["items"]["required"].as_array().map(Vec::len),
+            Some(5)
+        );
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("juventus.schema.json");
+        std::fs::write(&path, schema.to_string())?;
+        let errors = process_schema_validate(&path.to_string_lossy(), "assets/juventus.csv", None)?;
+        assert!(errors.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_error_path() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("players.json");
+        std::fs::write(&path, r#"[{"Name": "A", "Kit Number": 1}]"#)?;
+        let errors =
+            process_schema_validate("fixture/players.schema.json", &path.to_string_lossy(), None)?;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "/0/Kit Number");
+        Ok(())
+    }
+}
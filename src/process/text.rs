@@ -1,11 +1,10 @@
-use anyhow::Result;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
-use ed25519_dalek::{Signer, SigningKey, Verifier, VerifyingKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::rngs::OsRng;
 use std::path::PathBuf;
 use std::{collections::HashMap, io::Read};
 
-use crate::process_genpass;
+use crate::{key_bytes, process_genpass, CryptoError, KeyError, PasswordPolicy, Result};
 
 use crate::cli::SignatureFormat;
 
@@ -45,13 +44,18 @@ impl Blake3 {
     }
 
     fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
-        let key = key.as_ref();
-        let key = (&key[..32]).try_into()?;
-        Ok(Self::new(key))
+        Ok(Self::new(key_bytes(key.as_ref())?))
     }
 
     fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
-        let key = process_genpass(32, false, false, false, false)?;
+        let key = process_genpass(
+            &PasswordPolicy {
+                length: 32,
+                ..Default::default()
+            },
+            false,
+        )?
+        .password;
         let mut map = HashMap::new();
         map.insert("blake3.key", key.as_bytes().to_vec());
         Ok(map)
@@ -73,9 +77,7 @@ impl Ed25519Signer {
     }
 
     fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
-        let key = key.as_ref();
-        let key = (&key[..32]).try_into()?;
-        Ok(Self::new(key))
+        Ok(Self::new(&key_bytes(key.as_ref())?))
     }
 
     fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
@@ -91,9 +93,8 @@ impl Ed25519Signer {
 
 impl Ed25519Verifier {
     fn try_new(key: impl AsRef<[u8]>) -> Result<Self> {
-        let key = key.as_ref();
-        let key = (&key[..32]).try_into()?;
-        let key = VerifyingKey::from_bytes(key)?;
+        let key = VerifyingKey::from_bytes(&key_bytes(key.as_ref())?)
+            .map_err(|e| KeyError::Invalid(e.to_string()))?;
         Ok(Self { key })
     }
 }
@@ -111,7 +112,11 @@ impl TextVerifier for Ed25519Verifier {
     fn verify(&self, reader: &mut dyn Read, sig: &[u8]) -> Result<bool> {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
-        let sig = sig[..64].try_into()?;
+        let sig: [u8; 64] = sig.try_into().map_err(|_| CryptoError::SignatureLength {
+            expected: 64,
+            actual: sig.len(),
+        })?;
+        let sig = Signature::from_bytes(&sig);
         Ok(self.key.verify(&buf, &sig).is_ok())
     }
 }
@@ -142,21 +147,26 @@ pub fn process_verify(
     Ok(result)
 }
 
-pub fn process_generate(path: PathBuf, format: SignatureFormat) -> Result<()> {
+pub fn process_generate(path: PathBuf, format: SignatureFormat) -> Result<Vec<PathBuf>> {
     let map = match format {
         SignatureFormat::Blake3 => Blake3::generate()?,
 
         SignatureFormat::Ed25519 => Ed25519Signer::generate()?,
     };
+    let mut files = Vec::new();
     for (filename, key) in map {
-        std::fs::write(path.join(filename), key)?;
+        let file = path.join(filename);
+        std::fs::write(&file, key)?;
+        files.push(file);
     }
-    Ok(())
+    files.sort();
+    Ok(files)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Error;
 
     // #[test]
     // fn test_process_generate() {
@@ -187,4 +197,31 @@ mod tests {
         assert!(ret);
         Ok(())
     }
+
+    #[test]
+    fn test_invalid_key_and_signature_length() -> Result<()> {
+        let format = SignatureFormat::Blake3;
+        let err = process_sign(&mut "hello".as_bytes(), b"short", format).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Key(KeyError::InvalidLength {
+                expected: 32,
+                actual: 5
+            })
+        ));
+
+        let key: &[u8] = include_bytes!("../../fixture/ed25519.vk");
+        let err = process_verify(
+            &mut "hello".as_bytes(),
+            key,
+            &[0; 10],
+            SignatureFormat::Ed25519,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Crypto(CryptoError::SignatureLength { .. })
+        ));
+        Ok(())
+    }
 }
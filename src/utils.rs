@@ -1,5 +1,9 @@
-use anyhow::Result;
-use std::{fs::File, io::Read};
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+};
+
+use crate::{KeyError, Result};
 
 pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
     let reader: Box<dyn Read> = if input == "-" {
@@ -16,3 +20,25 @@ pub fn get_content(input: &str) -> Result<Vec<u8>> {
     reader.read_to_end(&mut buf)?;
     Ok(buf)
 }
+
+pub fn get_writer(output: &str) -> Result<Box<dyn Write>> {
+    let writer: Box<dyn Write> = if output == "-" {
+        Box::new(BufWriter::new(std::io::stdout()))
+    } else {
+        Box::new(BufWriter::new(File::create(output)?))
+    };
+    Ok(writer)
+}
+
+/// Read a fixed-size key, tolerating the trailing newline an editor or `echo` adds.
+pub(crate) fn key_bytes<const N: usize>(key: &[u8]) -> Result<[u8; N], KeyError> {
+    let trimmed = key
+        .strip_suffix(b"\r\n")
+        .or_else(|| key.strip_suffix(b"\n"))
+        .unwrap_or(key);
+    let key = if key.len() == N { key } else { trimmed };
+    key.try_into().map_err(|_| KeyError::InvalidLength {
+        expected: N,
+        actual: key.len(),
+    })
+}
@@ -0,0 +1,114 @@
+//! Field names printed by `--output-format json` are a stable interface for scripts.
+
+use std::process::Command;
+
+use serde_json::Value;
+
+fn rcli_json(args: &[&str]) -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_rcli"))
+        .args(args)
+        .args(["--output-format", "json"])
+        .output()
+        .expect("run rcli");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("JSON on stdout")
+}
+
+fn keys(value: &Value) -> Vec<&str> {
+    let mut keys = value
+        .as_object()
+        .expect("JSON object")
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn test_gen_fields() {
+    let token = rcli_json(&["gen", "token"]);
+    assert_eq!(keys(&token), ["token"]);
+    let uuid = rcli_json(&["gen", "uuid"]);
+    assert_eq!(keys(&uuid), ["uuid"]);
+    assert_eq!(uuid["uuid"].as_str().map(str::len), Some(36));
+}
+
+#[test]
+fn test_otp_code_fields() {
+    // RFC 4226 appendix D, counter 0
+    let secret = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+    let code = rcli_json(&["otp", "code", "-s", secret, "-c", "0"]);
+    assert_eq!(code, serde_json::json!({ "code": "755224" }));
+}
+
+#[test]
+fn test_text_sign_verify_fields() {
+    let sign = rcli_json(&[
+        "text",
+        "sign",
+        "-i",
+        "Cargo.toml",
+        "-k",
+        "fixture/ed25519.sk",
+        "-f",
+        "ed25519",
+    ]);
+    assert_eq!(keys(&sign), ["signature"]);
+    let signature = sign["signature"].as_str().expect("signature string");
+
+    let verify = rcli_json(&[
+        "text",
+        "verify",
+        "-i",
+        "Cargo.toml",
+        "-k",
+        "fixture/ed25519.vk",
+        "-s",
+        signature,
+        "--format",
+        "ed25519",
+    ]);
+    assert_eq!(verify, serde_json::json!({ "verified": true }));
+}
+
+#[test]
+fn test_csv_output_fields() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let output = dir.path().join("juventus.json");
+    let output = output.to_str().expect("utf-8 path");
+    let ret = rcli_json(&["csv", "-i", "assets/juventus.csv", "-o", output]);
+    assert_eq!(ret, serde_json::json!({ "output": output, "rows": 27 }));
+}
+
+#[test]
+fn test_diff_fields() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let write = |name: &str, content: &str| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).expect("write input");
+        path.to_str().expect("utf-8 path").to_string()
+    };
+    let a = write("a.csv", "Name,Kit\nPerin,37\nDybala,10\n");
+    let b = write("b.csv", "Name,Kit\nDybala,21\nPerin,37\n");
+
+    let by_index = rcli_json(&["diff", &a, &b]);
+    let by_key = rcli_json(&["diff", &a, &b, "--key", "Name"]);
+    assert_eq!(keys(&by_index), ["changes"]);
+    assert_eq!(by_index["changes"].as_array().map(Vec::len), Some(4));
+    assert_eq!(
+        by_key,
+        serde_json::json!({
+            "changes": [
+                { "op": "replace", "path": "/Dybala/Kit", "old": "10", "new": "21" }
+            ]
+        })
+    );
+    for entry in by_index["changes"].as_array().expect("changes array") {
+        assert_eq!(keys(entry), ["new", "old", "op", "path"]);
+    }
+}